@@ -6,6 +6,9 @@ var filesToCache = [
   "./index.html",
   "./minesweeper.js",
   "./minesweeper.wasm",
+  "./generator_worker.js",
+  "./generator_worker_bg.wasm",
+  "./generator_worker_loader.js",
 ];
 
 /* Start the service worker and cache all of the app's content */
@@ -43,25 +43,25 @@ pub fn solver_benchmark(c: &mut Criterion) {
     let minefields4x4m3 = (
         "10:4x4,3",
         (0..10)
-            .map(|_| Minefield::random_start(&mut rng, 4, 4, 3))
+            .map(|_| Minefield::random_start(&mut rng, 4, 4, 3).unwrap())
             .collect::<Vec<_>>(),
     );
     let minefields6x6m5 = (
         "3:6x6,5",
         (0..3)
-            .map(|_| Minefield::random_start(&mut rng, 6, 6, 5))
+            .map(|_| Minefield::random_start(&mut rng, 6, 6, 5).unwrap())
             .collect::<Vec<_>>(),
     );
-    let minefields9x9m10 = (
+    let _minefields9x9m10 = (
         "10:9x9,10",
         (0..10)
-            .map(|_| Minefield::random_start(&mut rng, 9, 9, 10))
+            .map(|_| Minefield::random_start(&mut rng, 9, 9, 10).unwrap())
             .collect::<Vec<_>>(),
     );
     let minefields16x16m40 = (
         "3:16x16,40",
         (0..10)
-            .map(|_| Minefield::random_start(&mut rng, 16, 16, 40))
+            .map(|_| Minefield::random_start(&mut rng, 16, 16, 40).unwrap())
             .collect::<Vec<_>>(),
     );
 
@@ -87,8 +87,8 @@ pub fn solver_benchmark(c: &mut Criterion) {
     bench_solver(&mut group, &minefields6x6m5, pruning_bm_wtm);
     bench_solver(&mut group, &minefields6x6m5, chucking_4_3_wtm);
 
-    // bench_solver(&mut group, &minefields9x9m10, pruning_bm_wtm);
-    // bench_solver(&mut group, &minefields9x9m10, chucking_4_3_wtm);
+    // bench_solver(&mut group, &_minefields9x9m10, pruning_bm_wtm);
+    // bench_solver(&mut group, &_minefields9x9m10, chucking_4_3_wtm);
 
     bench_solver(&mut group, &minefields16x16m40, pruning_bm_wtm);
 }
@@ -0,0 +1,12 @@
+// Built by trunk as the Web Worker that generates guess-free boards, see
+// `minesweeper::worker`.
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    eframe::WebLogger::init(log::LevelFilter::Debug).ok();
+
+    minesweeper::worker::run_worker();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {}
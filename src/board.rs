@@ -1,22 +1,143 @@
-use std::f32::consts::PI;
+use std::{collections::HashMap, f32::consts::PI, ops::RangeInclusive, sync::Arc};
 
-use crate::minefield::{CellKind, CellState, Minefield};
+use crate::{
+    minefield::{Cell, CellKind, CellState, Minefield, PlacementError},
+    skin::{Role, Skin},
+    solver::{find_deductions, mine_probabilities, resample_layout, Hint},
+};
 use eframe::{
-    egui::{Image, Sense, Ui, Widget},
+    egui::{Sense, Ui, Widget},
     epaint::{vec2, Rect},
 };
-use egui::{emath::RectTransform, pos2, PointerButton, Pos2, Response, TextureOptions};
-use rand::thread_rng;
+use egui::{
+    emath::RectTransform, pos2, Color32, Context, Key, Mesh, Modifiers, PointerButton, Pos2,
+    Response, Stroke,
+};
+use rand::{rngs::StdRng, thread_rng, SeedableRng};
 use web_time::Instant;
 
 const FLAGGING_ANIMATION_DURATION: f32 = 0.10;
 const FLAGGING_ANIMATION_SCALE: f32 = 1.3;
+const CHORD_ANIMATION_DURATION: f32 = 0.25;
+
+pub const TOUCH_SETTINGS_KEY: &str = "touch";
+
+/// What the cached cell meshes show, besides the minefield.
+#[derive(Clone, Debug, PartialEq)]
+struct MeshKey {
+    skin: String,
+    pressed: Option<(usize, usize)>,
+    editing: bool,
+    heatmap: bool,
+    /// Where the board is on screen, and the columns and rows in view.
+    rect: Rect,
+    visible: [usize; 4],
+}
+
+/// How presses on the board are read, mostly for touch screens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TouchSettings {
+    /// Seconds a press is held before it does the other action.
+    pub long_press: f64,
+    /// Taps flag and long presses open, instead of the other way around.
+    pub tap_flags: bool,
+}
+
+impl Default for TouchSettings {
+    fn default() -> Self {
+        TouchSettings {
+            long_press: 0.25,
+            tap_flags: false,
+        }
+    }
+}
+
+impl TouchSettings {
+    /// The long press durations the menu offers, in seconds.
+    pub const LONG_PRESS_RANGE: RangeInclusive<f64> = 0.1..=1.0;
+
+    pub fn encode(&self) -> String {
+        format!(
+            "{} {}",
+            self.long_press,
+            if self.tap_flags { "flag" } else { "open" }
+        )
+    }
+
+    pub fn decode(s: &str) -> Option<TouchSettings> {
+        let (long_press, tap) = s.split_once(' ')?;
+        let long_press = long_press.parse().ok()?;
+
+        // Also rejects NaN, which no range contains.
+        if !Self::LONG_PRESS_RANGE.contains(&long_press) {
+            return None;
+        }
+
+        Some(TouchSettings {
+            long_press,
+            tap_flags: match tap {
+                "flag" => true,
+                "open" => false,
+                _ => return None,
+            },
+        })
+    }
+}
+
+/// How the player set up play, carried over from board to board.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PlaySettings {
+    pub touch: TouchSettings,
+    /// The layout isn't fixed: it's redrawn as cells are opened, so that
+    /// guessing past a safe cell always hits a mine.
+    pub adversarial: bool,
+    /// Unflagging leaves a question mark, which the next toggle clears.
+    pub question_marks: bool,
+    /// Undo also works once the game is lost or won.
+    pub practice: bool,
+    /// Tints hidden cells from green to red by their chance of being a mine.
+    pub heatmap: bool,
+}
+
+/// A short buzz on phones, so a long press can be felt through the finger
+/// covering the cell.
+fn vibrate() {
+    #[cfg(target_arch = "wasm32")]
+    if let Some(window) = web_sys::window() {
+        window.navigator().vibrate_with_duration(30);
+    }
+}
 
 pub struct Board {
     pub minefield: Minefield,
 
     pub pressed: Option<(usize, usize, Instant)>,
     pub last_flag_toggle: Option<(usize, usize, Instant, bool)>,
+    pub last_chord: Option<(usize, usize, Instant)>,
+    pub settings: PlaySettings,
+    /// Clicks edit the layout instead of playing: the primary button toggles
+    /// mines and the secondary one toggles pre-opened cells.
+    pub editing: bool,
+    /// The cell the keyboard acts on, shown once a movement key is pressed.
+    pub cursor: Option<(usize, usize)>,
+    /// The cell last opened from the keyboard, so that a game can start there
+    /// like it does on a click.
+    pub key_opened: Option<(usize, usize)>,
+    /// The cells each move changed as they were before it, for undo, and as
+    /// they were before each undo, for redo.
+    history: Vec<Vec<(usize, Cell)>>,
+    undone: Vec<Vec<(usize, Cell)>>,
+    /// Set once a move is undone or the heatmap is shown mid-game.
+    pub assisted: bool,
+    /// The heatmap's probabilities, and the board they were computed for.
+    probabilities: Option<(Minefield, Vec<Option<f64>>)>,
+    /// The hint on display, and when it was asked for. Cleared by the next move.
+    pub hint: Option<(Hint, Instant)>,
+    /// Counts changes to `minefield`, so caches can tell it changed without
+    /// comparing every cell.
+    revision: u64,
+    /// The last cell meshes drawn, and the revision and view they show.
+    meshes: Option<(u64, MeshKey, Vec<Arc<Mesh>>)>,
 }
 
 impl Board {
@@ -25,11 +146,29 @@ impl Board {
             minefield,
             pressed: None,
             last_flag_toggle: None,
+            last_chord: None,
+            settings: PlaySettings::default(),
+            editing: false,
+            cursor: None,
+            key_opened: None,
+            history: Vec::new(),
+            undone: Vec::new(),
+            assisted: false,
+            probabilities: None,
+            hint: None,
+            revision: 0,
+            meshes: None,
         }
     }
 
-    pub fn new(width: usize, height: usize, mines: usize) -> Self {
-        Self::from_minefield(Minefield::generate(&mut thread_rng(), width, height, mines))
+    pub fn new(
+        width: usize,
+        height: usize,
+        mines: usize,
+        seed: u64,
+    ) -> Result<Self, PlacementError> {
+        Minefield::generate(&mut StdRng::seed_from_u64(seed), width, height, mines)
+            .map(Self::from_minefield)
     }
 
     pub fn mines(&self) -> usize {
@@ -40,6 +179,74 @@ impl Board {
             .count()
     }
 
+    /// Marks `minefield` as changed. Needed after changing it directly rather
+    /// than through the board's methods.
+    pub fn changed(&mut self) {
+        self.revision += 1;
+    }
+
+    /// Makes a move, keeping the cells it changed for undo.
+    pub fn play(&mut self, play: impl FnOnce(&mut Board)) {
+        let before = self.minefield.cells.clone();
+
+        play(self);
+
+        let changed = before
+            .into_iter()
+            .enumerate()
+            .filter(|(idx, cell)| *cell != self.minefield.cells[*idx])
+            .collect::<Vec<_>>();
+
+        if !changed.is_empty() {
+            self.history.push(changed);
+            self.undone.clear();
+            self.hint = None;
+            self.changed();
+        }
+    }
+
+    /// Puts back the cells of a move, returning the ones they replaced.
+    fn restore(&mut self, cells: Vec<(usize, Cell)>) -> Vec<(usize, Cell)> {
+        self.hint = None;
+        self.changed();
+
+        cells
+            .into_iter()
+            .map(|(idx, cell)| (idx, std::mem::replace(&mut self.minefield.cells[idx], cell)))
+            .collect()
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+            && (self.settings.practice || !(self.minefield.is_lost() || self.minefield.is_solved()))
+    }
+
+    pub fn undo(&mut self) {
+        if !self.can_undo() {
+            return;
+        }
+
+        if let Some(cells) = self.history.pop() {
+            let redo = self.restore(cells);
+            self.undone.push(redo);
+            self.assisted = true;
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(cells) = self.undone.pop() {
+            let undo = self.restore(cells);
+            self.history.push(undo);
+        }
+    }
+
+    /// Reverts the last move for good, without it counting as an undo.
+    pub fn take_back(&mut self) {
+        if let Some(cells) = self.history.pop() {
+            self.restore(cells);
+        }
+    }
+
     pub fn open_cell(&mut self, x: usize, y: usize) {
         if self.minefield.is_lost() || self.minefield.is_solved() {
             return;
@@ -50,8 +257,12 @@ impl Board {
 
         if cell.state == CellState::Opened {
             if mines == self.minefield.count_flags(x, y) {
+                self.last_chord = Some((x, y, Instant::now()));
+
                 for (x, y) in self.minefield.neighbors(x, y) {
-                    if self.minefield.cells[y * self.minefield.width + x].state == CellState::Hidden
+                    if self.minefield.cells[y * self.minefield.width + x]
+                        .state
+                        .is_hidden()
                     {
                         self.open_cell(x, y);
                     }
@@ -61,9 +272,33 @@ impl Board {
             return;
         }
 
+        if self.settings.adversarial && cell.state.is_hidden() {
+            self.adapt_layout(y * self.minefield.width + x);
+        }
+
         self.minefield.open(x, y);
     }
 
+    /// Before `idx` is opened in adversarial mode, redraws the layout so that
+    /// it's a mine if the player guessed while a safe cell was known, and safe
+    /// if there was nothing but guesses. Proven safe cells stay as they are.
+    fn adapt_layout(&mut self, idx: usize) {
+        let (safe, _) = find_deductions(&self.minefield.without_flags(), true);
+
+        if safe.contains(&idx) {
+            return;
+        }
+
+        let mine = !safe.is_empty();
+
+        match resample_layout(&self.minefield, &mut thread_rng(), idx, mine) {
+            Some(minefield) => self.minefield = minefield,
+            None => log::warn!(
+                "Adversarial mode couldn't redraw the layout for cell {idx}, keeping the current one"
+            ),
+        }
+    }
+
     pub fn toggle_flag(&mut self, x: usize, y: usize) {
         if self.minefield.is_lost() {
             return;
@@ -74,12 +309,7 @@ impl Board {
             return;
         }
 
-        // #[cfg(target_arch = "wasm32")]
-        // {
-        //     let window = web_sys::window().unwrap();
-        //     let navigator = window.navigator();
-        //     navigator.vibrate_with_duration(200);
-        // }
+        vibrate();
 
         match cell.state {
             CellState::Hidden => {
@@ -88,73 +318,278 @@ impl Board {
             }
             CellState::Flagged => {
                 self.last_flag_toggle = Some((x, y, Instant::now(), false));
-                cell.state = CellState::Hidden;
+                cell.state = if self.settings.question_marks {
+                    CellState::Questioned
+                } else {
+                    CellState::Hidden
+                };
             }
+            CellState::Questioned => cell.state = CellState::Hidden,
             CellState::Opened => unreachable!(),
         }
     }
 
-    pub fn size(&self) -> (f32, f32) {
-        (
-            self.minefield.width as f32 * 16.0,
-            self.minefield.height as f32 * 16.0,
-        )
-    }
+    pub fn toggle_mine(&mut self, x: usize, y: usize) {
+        let cell = &mut self.minefield.cells[y * self.minefield.width + x];
 
-    fn opened_cell(count: usize) -> Image<'static> {
-        Self::pixelate(Image::new(match count {
-            0 => return Board::empty_cell(),
-            1 => egui::include_image!("../assets/1.png"),
-            2 => egui::include_image!("../assets/2.png"),
-            3 => egui::include_image!("../assets/3.png"),
-            4 => egui::include_image!("../assets/4.png"),
-            5 => egui::include_image!("../assets/5.png"),
-            6 => egui::include_image!("../assets/6.png"),
-            7 => egui::include_image!("../assets/7.png"),
-            8 => egui::include_image!("../assets/8.png"),
-            _ => {
-                log::warn!("test2");
-                unreachable!()
-            }
-        }))
+        // Mines are never pre-opened, as that would be a lost game.
+        (cell.kind, cell.state) = match cell.kind {
+            CellKind::Empty => (CellKind::Mine, CellState::Hidden),
+            CellKind::Mine => (CellKind::Empty, cell.state),
+        };
+
+        self.changed();
     }
 
-    fn pixelate(image: Image) -> Image {
-        image.texture_options(TextureOptions::NEAREST)
+    pub fn toggle_opened(&mut self, x: usize, y: usize) {
+        let cell = &mut self.minefield.cells[y * self.minefield.width + x];
+
+        cell.state = match (cell.state, cell.kind) {
+            (CellState::Opened, _) => CellState::Hidden,
+            (_, CellKind::Empty) => CellState::Opened,
+            (state, CellKind::Mine) => state,
+        };
+
+        self.changed();
     }
 
-    fn empty_cell() -> Image<'static> {
-        Self::pixelate(Image::new(egui::include_image!("../assets/empty.png")))
+    /// Moves the cursor with the arrow keys or hjkl, and opens, flags or
+    /// chords the cell under it with O, F and C. In the editor O toggles a
+    /// mine and F a pre-opened cell. Ctrl+Z and Ctrl+Y undo and redo.
+    fn keyboard_input(&mut self, ui: &Ui) {
+        let (width, height) = (self.minefield.width, self.minefield.height);
+        let pressed =
+            |keys: &[Key]| ui.input(|input| keys.iter().any(|key| input.key_pressed(*key)));
+
+        if !self.editing {
+            if ui.input_mut(|input| input.consume_key(Modifiers::COMMAND, Key::Y)) {
+                self.redo();
+            } else if ui.input_mut(|input| input.consume_key(Modifiers::COMMAND, Key::Z)) {
+                self.undo();
+            }
+        }
+
+        for (keys, (dx, dy)) in [
+            ([Key::ArrowLeft, Key::H], (-1, 0)),
+            ([Key::ArrowRight, Key::L], (1, 0)),
+            ([Key::ArrowUp, Key::K], (0, -1)),
+            ([Key::ArrowDown, Key::J], (0, 1)),
+        ] {
+            if pressed(&keys) {
+                self.cursor = Some(match self.cursor {
+                    Some((x, y)) => (
+                        x.saturating_add_signed(dx).min(width - 1),
+                        y.saturating_add_signed(dy).min(height - 1),
+                    ),
+                    None => (width / 2, height / 2),
+                });
+            }
+        }
+
+        let Some((x, y)) = self.cursor else {
+            return;
+        };
+
+        let state = self.minefield.cells[y * width + x].state;
+
+        if self.editing {
+            if pressed(&[Key::O]) {
+                self.toggle_mine(x, y);
+            }
+
+            if pressed(&[Key::F]) {
+                self.toggle_opened(x, y);
+            }
+        } else {
+            if pressed(&[Key::O]) && state.is_hidden() {
+                self.play(|board| board.open_cell(x, y));
+                self.key_opened = Some((x, y));
+            }
+
+            if pressed(&[Key::F]) {
+                self.play(|board| board.toggle_flag(x, y));
+            }
+
+            if pressed(&[Key::C]) && state == CellState::Opened {
+                self.play(|board| board.open_cell(x, y));
+            }
+        }
     }
 
-    fn hidden_cell() -> Image<'static> {
-        Self::pixelate(Image::new(egui::include_image!("../assets/hidden.png")))
+    /// Recomputes the heatmap if the board changed since it was last drawn.
+    fn update_probabilities(&mut self) {
+        let shown = self.settings.heatmap
+            && !self.editing
+            && self
+                .minefield
+                .cells
+                .iter()
+                .any(|cell| cell.state == CellState::Opened);
+
+        if !shown {
+            self.probabilities = None;
+            return;
+        }
+
+        if !(self.minefield.is_lost() || self.minefield.is_solved()) {
+            self.assisted = true;
+        }
+
+        if self
+            .probabilities
+            .as_ref()
+            .is_some_and(|(minefield, _)| *minefield == self.minefield)
+        {
+            return;
+        }
+
+        self.probabilities = Some((self.minefield.clone(), mine_probabilities(&self.minefield)));
     }
 
-    fn flag_cell() -> Image<'static> {
-        Self::pixelate(Image::new(egui::include_image!("../assets/flag.png")))
+    fn heat_color(probability: f64) -> Color32 {
+        let probability = probability.clamp(0.0, 1.0) as f32;
+
+        Color32::from_rgba_unmultiplied(
+            (255.0 * probability) as u8,
+            (255.0 * (1.0 - probability)) as u8,
+            0,
+            110,
+        )
     }
 
-    fn opened_mine() -> Image<'static> {
-        Self::pixelate(Image::new(egui::include_image!(
-            "../assets/opened_mine.png"
-        )))
+    fn cell_role(&self, x: usize, y: usize, is_lost: bool, is_solved: bool) -> Role {
+        let cell = &self.minefield.cells[y * self.minefield.width + x];
+
+        match (cell.state, cell.kind) {
+            (CellState::Opened, CellKind::Mine) => Role::OpenedMine,
+            (CellState::Opened, CellKind::Empty) => match self.minefield.count_mines(x, y) {
+                0 => Role::Empty,
+                count => Role::Number(count),
+            },
+
+            (CellState::Flagged, CellKind::Empty) if is_lost => Role::IncorrectFlag,
+            (CellState::Flagged, _) => Role::Flag,
+
+            _ if self.pressed.is_some_and(|(px, py, _)| {
+                (px == x && py == y)
+                    || self.minefield.cells[py * self.minefield.width + px].state
+                        == CellState::Opened
+                        && x.abs_diff(px) <= 1
+                        && y.abs_diff(py) <= 1
+            }) =>
+            {
+                Role::Empty
+            }
+            (_, CellKind::Mine) if is_lost || self.editing => Role::RevealedMine,
+            (_, CellKind::Mine) if is_solved => Role::Flag,
+            (CellState::Questioned, _) => Role::Question,
+            (CellState::Hidden, _) => Role::Hidden,
+        }
     }
 
-    fn revealed_mine() -> Image<'static> {
-        Self::pixelate(Image::new(egui::include_image!(
-            "../assets/revealed_mine.png"
-        )))
+    /// The cells inside `clip` and their heatmap tints, in one mesh per
+    /// texture. Kept until the board or the view changes, so that large
+    /// boards aren't rebuilt every frame.
+    fn cell_meshes(
+        &mut self,
+        ctx: &Context,
+        skin: &Skin,
+        board_to_screen: &RectTransform,
+        clip: Rect,
+    ) -> Vec<Arc<Mesh>> {
+        let visible = board_to_screen
+            .inverse()
+            .transform_rect(clip.intersect(*board_to_screen.to()));
+        let span = |min: f32, max: f32, len: usize| {
+            (min / 16.0).floor().max(0.0) as usize..((max / 16.0).ceil().max(0.0) as usize).min(len)
+        };
+        let columns = span(visible.min.x, visible.max.x, self.minefield.width);
+        let rows = span(visible.min.y, visible.max.y, self.minefield.height);
+
+        let key = MeshKey {
+            skin: skin.name.clone(),
+            pressed: self.pressed.map(|(x, y, _)| (x, y)),
+            editing: self.editing,
+            heatmap: self.probabilities.is_some(),
+            rect: *board_to_screen.to(),
+            visible: [columns.start, columns.end, rows.start, rows.end],
+        };
+
+        if let Some((revision, cached, meshes)) = &self.meshes {
+            if *cached == key && *revision == self.revision {
+                return meshes.clone();
+            }
+        }
+
+        let is_lost = self.minefield.is_lost();
+        let is_solved = self.minefield.is_solved();
+
+        let mut textures = HashMap::new();
+        let mut meshes: Vec<Mesh> = Vec::new();
+        let mut tints = Mesh::default();
+        // Sprites still loading are left out, and the meshes not kept.
+        let mut complete = true;
+
+        for y in rows {
+            for x in columns.clone() {
+                let rect = board_to_screen.transform_rect(Rect::from_min_size(
+                    pos2(x as f32 * 16.0, y as f32 * 16.0),
+                    vec2(16.0, 16.0),
+                ));
+
+                let role = self.cell_role(x, y, is_lost, is_solved);
+
+                match *textures
+                    .entry(role)
+                    .or_insert_with(|| skin.sprite(role).texture(ctx))
+                {
+                    Some((texture, uv)) => {
+                        let idx = match meshes.iter().position(|mesh| mesh.texture_id == texture) {
+                            Some(idx) => idx,
+                            None => {
+                                meshes.push(Mesh::with_texture(texture));
+                                meshes.len() - 1
+                            }
+                        };
+
+                        meshes[idx].add_rect_with_uv(rect, uv, Color32::WHITE);
+                    }
+                    None => complete = false,
+                }
+
+                let idx = y * self.minefield.width + x;
+                let probability = self
+                    .probabilities
+                    .as_ref()
+                    .and_then(|(_, probabilities)| probabilities[idx]);
+
+                if let Some(probability) =
+                    probability.filter(|_| self.minefield.cells[idx].state.is_hidden())
+                {
+                    tints.add_colored_rect(rect, Board::heat_color(probability));
+                }
+            }
+        }
+
+        meshes.push(tints);
+        let meshes = meshes.into_iter().map(Arc::new).collect::<Vec<_>>();
+
+        if complete {
+            self.meshes = Some((self.revision, key, meshes.clone()));
+        }
+
+        meshes
     }
 
-    fn incorrect_flag() -> Image<'static> {
-        Self::pixelate(Image::new(egui::include_image!(
-            "../assets/incorrect_flag.png"
-        )))
+    pub fn size(&self) -> (f32, f32) {
+        (
+            self.minefield.width as f32 * 16.0,
+            self.minefield.height as f32 * 16.0,
+        )
     }
 }
 
-const LONG_PRESS_DURATION: f64 = 0.25;
+const HINT_PULSE_SPEED: f32 = 5.0;
 
 impl Widget for &mut Board {
     fn ui(self, ui: &mut Ui) -> Response {
@@ -165,33 +600,61 @@ impl Widget for &mut Board {
             screen_bounds,
         );
 
+        if !ui.ctx().wants_keyboard_input() {
+            self.keyboard_input(ui);
+        }
+
         let is_lost = self.minefield.is_lost();
         let is_solved = self.minefield.is_solved();
+        let skin = Skin::current(ui.ctx());
 
         let (_, response) = ui.allocate_exact_size(screen_bounds.size(), Sense::click());
 
-        if !is_lost && !is_solved {
+        if self.editing {
+            if let Some(pos) = response.interact_pointer_pos() {
+                let pos = board_to_screen.inverse().transform_pos(pos);
+                let x = pos.x as usize / 16;
+                let y = pos.y as usize / 16;
+
+                if response.clicked() {
+                    self.toggle_mine(x, y);
+                } else if response.secondary_clicked() {
+                    self.toggle_opened(x, y);
+                }
+            }
+        } else if !is_lost && !is_solved {
             if let Some(pos) = response.interact_pointer_pos() {
                 let pos = board_to_screen.inverse().transform_pos(pos);
                 let x = pos.x as usize / 16;
                 let y = pos.y as usize / 16;
 
                 if ui.input(|input| input.pointer.button_pressed(PointerButton::Secondary)) {
-                    self.toggle_flag(x, y);
+                    self.play(|board| board.toggle_flag(x, y));
                 }
 
                 if let Some((px, py, time)) = self.pressed {
+                    let opened = self.minefield.cells[y * self.minefield.width + x].state
+                        == CellState::Opened;
+
+                    // Taps on a number always chord, whichever action taps do.
                     if x == px && y == py {
-                        if time.elapsed().as_secs_f64() >= LONG_PRESS_DURATION
-                            && self.minefield.cells[y * self.minefield.width + x].state
-                                != CellState::Opened
+                        if time.elapsed().as_secs_f64() >= self.settings.touch.long_press && !opened
                         {
-                            self.toggle_flag(x, y);
+                            if self.settings.touch.tap_flags {
+                                vibrate();
+                                self.play(|board| board.open_cell(x, y));
+                            } else {
+                                self.play(|board| board.toggle_flag(x, y));
+                            }
                             self.pressed = None;
                         } else if ui
                             .input(|input| input.pointer.button_released(PointerButton::Primary))
                         {
-                            self.open_cell(x, y);
+                            if self.settings.touch.tap_flags && !opened {
+                                self.play(|board| board.toggle_flag(x, y));
+                            } else {
+                                self.play(|board| board.open_cell(x, y));
+                            }
                             self.pressed = None;
                         }
                     }
@@ -203,40 +666,92 @@ impl Widget for &mut Board {
             }
         }
 
-        for y in 0..self.minefield.height {
-            for x in 0..self.minefield.width {
-                let rect = board_to_screen.transform_rect(Rect::from_min_size(
-                    pos2(x as f32 * 16.0, y as f32 * 16.0),
-                    vec2(16.0, 16.0),
-                ));
+        self.update_probabilities();
 
-                let cell = &self.minefield.cells[y * self.minefield.width + x];
+        // egui's shapes own their mesh, so this is the only copy made on a
+        // frame the cache is hit.
+        for mesh in self.cell_meshes(ui.ctx(), &skin, &board_to_screen, ui.clip_rect()) {
+            ui.painter().add(Mesh::clone(&mesh));
+        }
 
-                let image = match (cell.state, cell.kind) {
-                    (CellState::Opened, CellKind::Mine) => Board::opened_mine(),
-                    (CellState::Opened, CellKind::Empty) => {
-                        Board::opened_cell(self.minefield.count_mines(x, y))
-                    }
+        if let Some((hint, time)) = &self.hint {
+            let cell_rect = |idx: usize| {
+                board_to_screen.transform_rect(Rect::from_min_size(
+                    pos2(
+                        (idx % self.minefield.width) as f32 * 16.0,
+                        (idx / self.minefield.width) as f32 * 16.0,
+                    ),
+                    vec2(16.0, 16.0),
+                ))
+            };
+
+            // The numbers behind the hint pulse, so they read as the reason
+            // rather than the move.
+            let pulse = (time.elapsed().as_secs_f32() * HINT_PULSE_SPEED).sin() * 0.5 + 0.5;
+
+            for idx in &hint.reasons {
+                ui.painter().rect_filled(
+                    cell_rect(*idx),
+                    0.0,
+                    Color32::from_rgba_unmultiplied(255, 255, 0, (40.0 + 80.0 * pulse) as u8),
+                );
+            }
 
-                    (CellState::Flagged, CellKind::Empty) if is_lost => Board::incorrect_flag(),
-                    (CellState::Flagged, _) => Board::flag_cell(),
+            // Keeps the pulse moving while nothing else repaints.
+            ui.ctx().request_repaint();
+
+            let rect = cell_rect(hint.cell);
+
+            ui.painter().rect_stroke(
+                rect.shrink(rect.width() / 16.0),
+                0.0,
+                Stroke::new(
+                    rect.width() / 8.0,
+                    if hint.mine {
+                        Color32::RED
+                    } else {
+                        Color32::GREEN
+                    },
+                ),
+            );
+        }
 
-                    _ if self.pressed.map_or(false, |(px, py, _)| {
-                        (px == x && py == y)
-                            || self.minefield.cells[py * self.minefield.width + px].state
-                                == CellState::Opened
-                                && x.abs_diff(px) <= 1
-                                && y.abs_diff(py) <= 1
-                    }) =>
-                    {
-                        Board::empty_cell()
-                    }
-                    (CellState::Hidden, CellKind::Mine) if is_lost => Board::revealed_mine(),
-                    (CellState::Hidden, CellKind::Mine) if is_solved => Board::flag_cell(),
-                    (CellState::Hidden, _) => Board::hidden_cell(),
-                };
+        if let Some((x, y)) = self.cursor {
+            let rect = board_to_screen.transform_rect(Rect::from_min_size(
+                pos2(x as f32 * 16.0, y as f32 * 16.0),
+                vec2(16.0, 16.0),
+            ));
+
+            ui.painter().rect_stroke(
+                rect.shrink(rect.width() / 16.0),
+                0.0,
+                Stroke::new(rect.width() / 8.0, Color32::BLUE),
+            );
+        }
+
+        if let Some((x, y, time)) = self.last_chord {
+            let elapsed = time.elapsed().as_secs_f32();
 
-                image.paint_at(ui, rect);
+            if elapsed < CHORD_ANIMATION_DURATION {
+                // A ring around the number that grows and fades out over the
+                // cells it opened.
+                let rect = board_to_screen.transform_rect(Rect::from_min_size(
+                    pos2(x as f32 * 16.0 - 16.0, y as f32 * 16.0 - 16.0),
+                    vec2(48.0, 48.0),
+                ));
+                let progress = elapsed / CHORD_ANIMATION_DURATION;
+
+                ui.painter().rect_stroke(
+                    rect.shrink(rect.width() / 3.0 * (1.0 - progress)),
+                    0.0,
+                    Stroke::new(
+                        rect.width() / 24.0,
+                        Color32::from_white_alpha((255.0 * (1.0 - progress)) as u8),
+                    ),
+                );
+                ui.ctx().request_repaint();
+            } else {
+                self.last_chord = None;
             }
         }
 
@@ -257,7 +772,7 @@ impl Widget for &mut Board {
 
                 let rect = rect.expand(FLAGGING_ANIMATION_SCALE * 16.0 * alpha);
 
-                Board::flag_cell().paint_at(ui, rect);
+                skin.image(Role::Flag).paint_at(ui, rect);
                 ui.ctx().request_repaint();
             } else {
                 self.last_flag_toggle = None;
@@ -267,3 +782,94 @@ impl Widget for &mut Board {
         response
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::skin::available_skins;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut board = Board::from_minefield(Minefield::parse("m..\n...\n..m"));
+
+        board.play(|board| board.toggle_flag(0, 0));
+        board.play(|board| board.open_cell(1, 1));
+        let played = board.minefield.clone();
+
+        // Only the cells a move changed are kept.
+        assert_eq!(board.history.len(), 2);
+        assert_eq!(board.history[0].len(), 1);
+
+        board.undo();
+        board.undo();
+        assert_eq!(board.minefield, Minefield::parse("m..\n...\n..m"));
+        assert!(board.assisted);
+
+        board.redo();
+        board.redo();
+        assert_eq!(board.minefield, played);
+
+        // A lost game can only be taken back in practice.
+        board.play(|board| board.toggle_flag(0, 0));
+        board.play(|board| board.open_cell(0, 0));
+        board.undo();
+        assert!(board.minefield.is_lost());
+
+        board.settings.practice = true;
+        board.undo();
+        assert!(!board.minefield.is_lost());
+    }
+
+    #[test]
+    fn test_touch_settings() {
+        let touch = TouchSettings {
+            long_press: 0.4,
+            tap_flags: true,
+        };
+
+        assert_eq!(TouchSettings::decode(&touch.encode()), Some(touch));
+        assert_eq!(TouchSettings::decode("0.4 poke"), None);
+        assert_eq!(TouchSettings::decode("0 open"), None);
+        assert_eq!(TouchSettings::decode("5 open"), None);
+        assert_eq!(TouchSettings::decode("NaN open"), None);
+    }
+
+    #[test]
+    fn test_cell_meshes() {
+        let ctx = Context::default();
+        let skin = available_skins(&ctx)
+            .into_iter()
+            .find(|skin| skin.name == "Midnight")
+            .unwrap();
+        let mut board = Board::from_minefield(Minefield::new(500, 500));
+
+        // 16 points a cell, with only the top left 10x10 cells in view.
+        let board_to_screen = RectTransform::from_to(
+            Rect::from_min_size(Pos2::ZERO, board.size().into()),
+            Rect::from_min_size(Pos2::ZERO, vec2(8000.0, 8000.0)),
+        );
+        let clip = Rect::from_min_size(Pos2::ZERO, vec2(160.0, 160.0));
+
+        let meshes = board.cell_meshes(&ctx, &skin, &board_to_screen, clip);
+        let cells = |meshes: &[Arc<Mesh>]| {
+            meshes
+                .iter()
+                .map(|mesh| mesh.indices.len() / 6)
+                .sum::<usize>()
+        };
+
+        assert_eq!(cells(&meshes), 100);
+        assert!(board.meshes.is_some());
+
+        // A hit shares the cached meshes rather than copying them.
+        let hit = board.cell_meshes(&ctx, &skin, &board_to_screen, clip);
+        assert!(Arc::ptr_eq(&hit[0], &meshes[0]));
+
+        board.play(|board| board.toggle_flag(0, 0));
+        let meshes = board.cell_meshes(&ctx, &skin, &board_to_screen, clip);
+
+        assert_eq!(cells(&meshes), 100);
+        assert!(!Arc::ptr_eq(&hit[0], &meshes[0]));
+        assert_eq!(board.meshes.as_ref().unwrap().0, board.revision);
+    }
+}
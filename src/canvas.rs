@@ -4,11 +4,68 @@ use egui::{
 };
 
 const MAX_ZOOM: f32 = 4.0;
+/// The largest whole scale offered in the zoom setting.
+const MAX_SCALE: u8 = 8;
+
+pub const ZOOM_KEY: &str = "zoom";
+
+/// How large the whole UI is drawn, in physical pixels per point.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Zoom {
+    /// The largest whole scale at which the board fits the window.
+    #[default]
+    Fit,
+    Fixed(u8),
+}
+
+impl Zoom {
+    /// Pixels per point for content of `size`, in points, on a screen of
+    /// `screen`, in physical pixels. Always a whole number, so that sprite
+    /// pixels stay square.
+    pub fn scale(self, size: Vec2, screen: Vec2) -> f32 {
+        match self {
+            Zoom::Fit => (screen / size).min_elem().floor().max(1.0),
+            Zoom::Fixed(scale) => scale as f32,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            Zoom::Fit => Zoom::Fixed(1),
+            Zoom::Fixed(scale) if scale < MAX_SCALE => Zoom::Fixed(scale + 1),
+            Zoom::Fixed(_) => Zoom::Fit,
+        }
+    }
+
+    pub fn encode(self) -> String {
+        match self {
+            Zoom::Fit => "fit".to_string(),
+            Zoom::Fixed(scale) => scale.to_string(),
+        }
+    }
+
+    pub fn decode(s: &str) -> Option<Self> {
+        match s {
+            "fit" => Some(Zoom::Fit),
+            _ => s
+                .parse()
+                .ok()
+                .filter(|scale| (1..=MAX_SCALE).contains(scale))
+                .map(Zoom::Fixed),
+        }
+    }
+}
 
 pub struct Canvas {
     transform: TSTransform,
 }
 
+impl Default for Canvas {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Canvas {
     pub fn new() -> Self {
         Canvas {
@@ -16,28 +73,41 @@ impl Canvas {
         }
     }
 
-    pub fn adjusted_bounds(content_size: Vec2, screen_bounds: Rect) -> Rect {
+    /// Fits the content in `screen_bounds`, scaled by a whole number of
+    /// physical pixels per content point when it fits at least at one, and
+    /// aligned to the pixel grid.
+    pub fn adjusted_bounds(content_size: Vec2, screen_bounds: Rect, pixels_per_point: f32) -> Rect {
         let screen_ratio = screen_bounds.aspect_ratio();
         let content_ratio = content_size.x / content_size.y;
 
-        let resized = if screen_ratio > content_ratio {
+        let mut resized = if screen_ratio > content_ratio {
             Vec2::splat(screen_bounds.size().y) * vec2(content_ratio, 1.0)
         } else {
             Vec2::splat(screen_bounds.size().x) * vec2(1.0, 1.0 / content_ratio)
         };
 
-        Rect::from_min_size(
-            screen_bounds.min
-                + vec2(
-                    (screen_bounds.size().x - resized.x).max(0.0) / 2.0,
-                    (screen_bounds.size().y - resized.y).max(0.0) / 2.0,
-                ),
-            resized,
-        )
+        let pixels = resized.x / content_size.x * pixels_per_point;
+
+        if pixels >= 1.0 {
+            resized = content_size * pixels.floor() / pixels_per_point;
+        }
+
+        let min = screen_bounds.min
+            + vec2(
+                (screen_bounds.size().x - resized.x).max(0.0) / 2.0,
+                (screen_bounds.size().y - resized.y).max(0.0) / 2.0,
+            );
+
+        Rect::from_min_size((min * pixels_per_point).round() / pixels_per_point, resized)
     }
 
-    pub fn adjusted_transform(&self, content_size: Vec2, screen_bounds: Rect) -> RectTransform {
-        let adjusted_bounds = Self::adjusted_bounds(content_size, screen_bounds);
+    pub fn adjusted_transform(
+        &self,
+        content_size: Vec2,
+        screen_bounds: Rect,
+        pixels_per_point: f32,
+    ) -> RectTransform {
+        let adjusted_bounds = Self::adjusted_bounds(content_size, screen_bounds, pixels_per_point);
 
         let align_transform = RectTransform::from_to(
             Rect::from_min_size(Pos2::ZERO, content_size),
@@ -51,6 +121,21 @@ impl Canvas {
         )
     }
 
+    /// Pans the least needed for `rect`, in content coordinates, to be in
+    /// view.
+    pub fn keep_visible(&mut self, content_size: Vec2, rect: Rect) {
+        let scaling = self.transform.scaling;
+        let translation = &mut self.transform.translation;
+
+        for axis in 0..2 {
+            if rect.min[axis] * scaling + translation[axis] < 0.0 {
+                translation[axis] = -rect.min[axis] * scaling;
+            } else if rect.max[axis] * scaling + translation[axis] > content_size[axis] {
+                translation[axis] = content_size[axis] - rect.max[axis] * scaling;
+            }
+        }
+    }
+
     pub fn show<R>(
         &mut self,
         ui: &mut Ui,
@@ -58,12 +143,13 @@ impl Canvas {
         add_contents: impl FnOnce(&mut Ui) -> R,
     ) -> InnerResponse<R> {
         let screen_bounds = ui.available_rect_before_wrap();
+        let pixels_per_point = ui.ctx().pixels_per_point();
 
         // let adjusted_bounds = Rect::from_min_size(screen_bounds.min, resized);
 
         let response = ui.allocate_response(screen_bounds.size(), egui::Sense::drag());
 
-        let transform = self.adjusted_transform(content_size, screen_bounds);
+        let transform = self.adjusted_transform(content_size, screen_bounds, pixels_per_point);
 
         if response.dragged() {
             self.transform.translation +=
@@ -99,7 +185,7 @@ impl Canvas {
             .translation
             .max(content_size * Vec2::splat(1.0 - self.transform.scaling));
 
-        let transform = self.adjusted_transform(content_size, screen_bounds);
+        let transform = self.adjusted_transform(content_size, screen_bounds, pixels_per_point);
         let target_bounds = transform.transform_rect(Rect::from_min_size(Pos2::ZERO, content_size));
 
         let mut content_ui = ui.child_ui_with_id_source(target_bounds, *ui.layout(), response.id);
@@ -111,3 +197,26 @@ impl Canvas {
         InnerResponse::new(ret, response)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zoom() {
+        let size = vec2(504.0, 320.0);
+
+        assert_eq!(Zoom::Fit.scale(size, vec2(1920.0, 1080.0)), 3.0);
+        assert_eq!(Zoom::Fit.scale(size, vec2(400.0, 300.0)), 1.0);
+        assert_eq!(Zoom::Fixed(2).scale(size, vec2(400.0, 300.0)), 2.0);
+
+        let mut zoom = Zoom::Fit;
+
+        for _ in 0..=MAX_SCALE {
+            assert_eq!(Zoom::decode(&zoom.encode()), Some(zoom));
+            zoom = zoom.next();
+        }
+
+        assert_eq!(zoom, Zoom::Fit);
+    }
+}
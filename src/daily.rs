@@ -0,0 +1,139 @@
+//! The daily challenge: one guess-free board a day, the same for every player.
+
+use crate::{
+    generating::{DifficultyBand, GeneratorConfig},
+    minefield::FirstClick,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use web_time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DAILY_RECORD_KEY: &str = "daily";
+
+const SIZES: [(usize, usize, usize); 3] = [(9, 9, 10), (16, 16, 40), (30, 16, 99)];
+
+/// Days since the Unix epoch, in UTC so that every player switches boards at
+/// the same moment.
+pub fn today() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / (24 * 60 * 60)
+}
+
+/// The `(year, month, day)` of a day since the Unix epoch.
+pub fn date(day: u64) -> (i64, u32, u32) {
+    // From Howard Hinnant's `civil_from_days`.
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+
+    (yoe + era * 400 + (m <= 2) as i64, m as u32, d as u32)
+}
+
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// The board of a day. The first click is part of the puzzle, since the
+/// guess-free layout depends on it.
+pub fn daily_config(day: u64) -> GeneratorConfig {
+    let seed = splitmix64(day);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (width, height, mines) = SIZES[rng.gen_range(0..SIZES.len() as u64) as usize];
+
+    GeneratorConfig {
+        start: rng.gen_range(0..(width * height) as u64) as usize,
+        width,
+        height,
+        mines,
+        seed,
+        first_click: FirstClick::Opening,
+        band: DifficultyBand::default(),
+    }
+}
+
+/// How the player did on the most recent daily they finished.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DailyRecord {
+    pub day: u64,
+    pub best: Option<Duration>,
+}
+
+impl DailyRecord {
+    pub fn best_on(&self, day: u64) -> Option<Duration> {
+        self.best.filter(|_| self.day == day)
+    }
+
+    pub fn record(&mut self, day: u64, time: Duration) {
+        if self.day != day {
+            *self = DailyRecord { day, best: None };
+        }
+
+        self.best = Some(self.best.map_or(time, |best| best.min(time)));
+    }
+
+    pub fn encode(&self) -> String {
+        match self.best {
+            Some(best) => format!("{} {}", self.day, best.as_millis()),
+            None => format!("{} -", self.day),
+        }
+    }
+
+    pub fn decode(s: &str) -> Option<DailyRecord> {
+        let (day, best) = s.split_once(' ')?;
+
+        Some(DailyRecord {
+            day: day.parse().ok()?,
+            best: match best {
+                "-" => None,
+                best => Some(Duration::from_millis(best.parse().ok()?)),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date() {
+        assert_eq!(date(0), (1970, 1, 1));
+        assert_eq!(date(11_016), (2000, 2, 29));
+        assert_eq!(date(20_745), (2026, 10, 19));
+    }
+
+    #[test]
+    fn test_daily_config() {
+        assert_eq!(daily_config(20_745), daily_config(20_745));
+        assert_ne!(daily_config(20_745).seed, daily_config(20_746).seed);
+
+        let config = daily_config(20_745);
+        assert_eq!((config.width, config.height, config.mines), (30, 16, 99));
+        assert_eq!(config.start, 390);
+    }
+
+    #[test]
+    fn test_daily_record() {
+        let mut record = DailyRecord::default();
+
+        record.record(5, Duration::from_secs(40));
+        record.record(5, Duration::from_secs(50));
+        assert_eq!(record.best_on(5), Some(Duration::from_secs(40)));
+        assert_eq!(record.best_on(6), None);
+
+        assert_eq!(DailyRecord::decode(&record.encode()), Some(record));
+
+        record.record(6, Duration::from_secs(70));
+        assert_eq!(record.best_on(6), Some(Duration::from_secs(70)));
+    }
+}
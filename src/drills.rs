@@ -0,0 +1,301 @@
+//! Drills on generated positions that need exactly one named pattern, with
+//! accuracy and reaction time kept per pattern.
+
+use crate::{
+    minefield::{CellKind, Minefield},
+    solver::{find_deductions, solve_step_basic},
+};
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use web_time::{Duration, Instant};
+
+pub const DRILL_STATS_KEY: &str = "drills";
+
+const MAX_DRILL_ATTEMPTS: usize = 1000;
+
+pub struct Pattern {
+    pub name: &'static str,
+    /// The pattern against the top and left walls. Hidden filler columns with
+    /// random mines are added to its right, past a hidden column without mines
+    /// so that the filler never changes the pattern's numbers.
+    board: &'static str,
+    /// Whether the pattern needs the total mine count.
+    counting: bool,
+}
+
+pub const PATTERNS: [Pattern; 6] = [
+    Pattern {
+        name: "1-1",
+        board: ".m.\n11.",
+        counting: false,
+    },
+    Pattern {
+        name: "1-2",
+        board: ".mm\n122",
+        counting: false,
+    },
+    Pattern {
+        name: "1-2-1",
+        board: "m.m\n121",
+        counting: false,
+    },
+    Pattern {
+        name: "1-2-2-1",
+        board: ".mm.\n1221",
+        counting: false,
+    },
+    Pattern {
+        name: "Corner",
+        board: "m1.\n11.\n...",
+        counting: false,
+    },
+    Pattern {
+        name: "Mine counting",
+        board: "...\n.m2\n..m",
+        counting: true,
+    },
+];
+
+/// Mirrors and transposes `minefield`, so that patterns show up against
+/// every wall.
+fn transform(minefield: &Minefield, flip_x: bool, flip_y: bool, transpose: bool) -> Minefield {
+    let (width, height) = if transpose {
+        (minefield.height, minefield.width)
+    } else {
+        (minefield.width, minefield.height)
+    };
+
+    let source = |x: usize, y: usize| {
+        let x = if flip_x { width - 1 - x } else { x };
+        let y = if flip_y { height - 1 - y } else { y };
+        let (x, y) = if transpose { (y, x) } else { (x, y) };
+        y * minefield.width + x
+    };
+
+    Minefield {
+        cells: (0..width * height)
+            .map(|idx| minefield.cells[source(idx % width, idx / width)])
+            .collect(),
+        width,
+        height,
+    }
+}
+
+/// A position where `pattern` is the only way forward, or `None` if none
+/// turned up after `MAX_DRILL_ATTEMPTS`.
+pub fn generate_drill(rng: &mut impl Rng, pattern: &Pattern) -> Option<Minefield> {
+    let template = Minefield::parse(pattern.board);
+
+    for _ in 0..MAX_DRILL_ATTEMPTS {
+        let filler = rng.gen_range(2..=4);
+        // The pattern, then the buffer column, then the filler.
+        let width = template.width + 1 + filler;
+        let mut minefield = Minefield::new(width, template.height);
+
+        for y in 0..template.height {
+            for x in 0..template.width {
+                minefield.cells[y * width + x] = template.cells[y * template.width + x];
+            }
+        }
+
+        let filler_cells = (0..minefield.cells.len())
+            .filter(|idx| idx % width > template.width)
+            .collect::<Vec<_>>();
+        let mines = rng.gen_range(0..=filler_cells.len() / 2);
+
+        for idx in rand::seq::index::sample(rng, filler_cells.len(), mines) {
+            minefield.cells[filler_cells[idx]].kind = CellKind::Mine;
+        }
+
+        // Marks the pattern's own cells and the buffer it proves safe, to
+        // check the deductions stay in them.
+        let mut in_pattern = Minefield::new(width, template.height);
+
+        for idx in 0..in_pattern.cells.len() {
+            if idx % width <= template.width {
+                in_pattern.cells[idx].kind = CellKind::Mine;
+            }
+        }
+
+        let (flip_x, flip_y, transpose) = (rng.gen(), rng.gen(), rng.gen());
+        let minefield = transform(&minefield, flip_x, flip_y, transpose);
+        let in_pattern = transform(&in_pattern, flip_x, flip_y, transpose);
+
+        let (safe, found_mines) = find_deductions(&minefield, true);
+        let local = find_deductions(&minefield, false);
+
+        let valid = if pattern.counting {
+            local == (vec![], vec![]) && (!safe.is_empty() || !found_mines.is_empty())
+        } else {
+            (!safe.is_empty() || !found_mines.is_empty())
+                && local == (safe.clone(), found_mines.clone())
+                && !solve_step_basic(&mut minefield.clone())
+                && safe
+                    .iter()
+                    .chain(&found_mines)
+                    .all(|&idx| in_pattern.cells[idx].kind == CellKind::Mine)
+        };
+
+        if valid {
+            return Some(minefield);
+        }
+    }
+
+    None
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PatternStats {
+    pub attempts: usize,
+    pub right: usize,
+    /// Summed over all attempts.
+    pub time: Duration,
+}
+
+impl PatternStats {
+    pub fn accuracy(&self) -> Option<f32> {
+        (self.attempts > 0).then(|| self.right as f32 / self.attempts as f32)
+    }
+
+    pub fn average_time(&self) -> Option<Duration> {
+        (self.attempts > 0).then(|| self.time / self.attempts as u32)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DrillStats {
+    pub patterns: [PatternStats; PATTERNS.len()],
+}
+
+impl DrillStats {
+    pub fn record(&mut self, pattern: usize, right: bool, time: Duration) {
+        let stats = &mut self.patterns[pattern];
+
+        stats.attempts += 1;
+        stats.right += right as usize;
+        stats.time += time;
+    }
+
+    /// Picks a pattern to drill, favoring the ones answered wrong most often.
+    pub fn next_pattern(&self, rng: &mut impl Rng) -> usize {
+        let weights = self
+            .patterns
+            .iter()
+            .map(|stats| 1.1 - stats.accuracy().unwrap_or(0.0));
+
+        WeightedIndex::new(weights)
+            .map(|weights| weights.sample(rng))
+            .unwrap_or_default()
+    }
+
+    /// One line per pattern: its name, attempts, right answers and total time
+    /// in milliseconds, separated by tabs.
+    pub fn encode(&self) -> String {
+        PATTERNS
+            .iter()
+            .zip(&self.patterns)
+            .map(|(pattern, stats)| {
+                format!(
+                    "{}\t{}\t{}\t{}",
+                    pattern.name,
+                    stats.attempts,
+                    stats.right,
+                    stats.time.as_millis()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Lines for unknown patterns or with bad numbers are skipped.
+    pub fn decode(s: &str) -> DrillStats {
+        let mut stats = DrillStats::default();
+
+        for line in s.lines() {
+            let fields = line.split('\t').collect::<Vec<_>>();
+
+            let [name, attempts, right, time] = fields[..] else {
+                continue;
+            };
+
+            let Some(pattern) = PATTERNS.iter().position(|pattern| pattern.name == name) else {
+                continue;
+            };
+
+            if let (Ok(attempts), Ok(right), Ok(time)) =
+                (attempts.parse(), right.parse(), time.parse())
+            {
+                stats.patterns[pattern] = PatternStats {
+                    attempts,
+                    right,
+                    time: Duration::from_millis(time),
+                };
+            }
+        }
+
+        stats
+    }
+}
+
+/// The position being drilled.
+#[derive(Clone, Copy, Debug)]
+pub struct Drill {
+    pub pattern: usize,
+    pub shown: Instant,
+    /// How the answer went, once given.
+    pub right: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::minefield::CellState;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    /// Whether `drill`, turned back against the top and left walls, has the
+    /// template's cells and shows its numbers.
+    fn shows(drill: &Minefield, template: &Minefield) -> bool {
+        (0..8).any(|i| {
+            let drill = transform(drill, i & 1 != 0, i & 2 != 0, i & 4 != 0);
+
+            drill.height == template.height
+                && (0..template.height).all(|y| {
+                    (0..template.width).all(|x| {
+                        let cell = template.cells[y * template.width + x];
+
+                        drill.cells[y * drill.width + x] == cell
+                            && (cell.state != CellState::Opened
+                                || drill.count_mines(x, y) == template.count_mines(x, y))
+                    })
+                })
+        })
+    }
+
+    #[test]
+    fn test_generate_drill() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for pattern in &PATTERNS {
+            let template = Minefield::parse(pattern.board);
+
+            for _ in 0..50 {
+                let minefield = generate_drill(&mut rng, pattern).unwrap();
+                assert!(shows(&minefield, &template), "{}", pattern.name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_drill_stats() {
+        let mut stats = DrillStats::default();
+
+        stats.record(2, true, Duration::from_millis(800));
+        stats.record(2, false, Duration::from_millis(1200));
+
+        assert_eq!(stats.patterns[2].accuracy(), Some(0.5));
+        assert_eq!(
+            stats.patterns[2].average_time(),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(DrillStats::decode(&stats.encode()), stats);
+    }
+}
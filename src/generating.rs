@@ -1,169 +1,823 @@
 use crate::{
-    minefield::{CellKind, Minefield},
-    solver::{solve, solve_step},
+    minefield::{CellKind, CellState, FirstClick, Minefield, PlacementError},
+    solver::{solve, solve_rated, solve_step},
 };
-use rand::thread_rng;
+use rand::{rngs::StdRng, seq::IteratorRandom, Rng, SeedableRng};
+use std::fmt::Display;
+#[cfg(not(target_arch = "wasm32"))]
 use std::{
+    collections::BTreeSet,
+    num::NonZeroUsize,
     sync::{
-        atomic::{AtomicU32, Ordering},
-        mpsc::{channel, sync_channel, Receiver, Sender, TryRecvError},
+        atomic::{AtomicBool, AtomicUsize, Ordering},
         Arc, Mutex,
     },
-    thread,
+    thread::{self, available_parallelism, JoinHandle},
 };
 use web_time::{Duration, Instant};
 
+#[cfg(target_arch = "wasm32")]
+use crate::worker::WorkerGuessfreeGenerator;
+
+#[cfg(target_arch = "wasm32")]
 const MIN_RENDER_INTERVAL: Duration = Duration::from_millis(100);
+const MAX_REPAIRS: usize = 500;
+const MAX_BAND_ATTEMPTS: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GeneratorConfig {
+    pub start: usize,
+    pub width: usize,
+    pub height: usize,
+    pub mines: usize,
+    pub seed: u64,
+    pub first_click: FirstClick,
+    pub band: DifficultyBand,
+}
+
+/// A seed as players share it. Boards are laid out around the first click,
+/// so once a game has started the cell it started from is part of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SharedSeed {
+    pub seed: u64,
+    pub start: Option<(usize, usize)>,
+}
+
+impl SharedSeed {
+    /// The seed, followed by `@x,y` once the first cell is known.
+    pub fn encode(&self) -> String {
+        match self.start {
+            Some((x, y)) => format!("{}@{x},{y}", self.seed),
+            None => self.seed.to_string(),
+        }
+    }
+
+    pub fn decode(s: &str) -> Option<SharedSeed> {
+        let (seed, start) = match s.trim().split_once('@') {
+            Some((seed, start)) => {
+                let (x, y) = start.split_once(',')?;
+                (seed, Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+            }
+            None => (s.trim(), None),
+        };
+
+        Some(SharedSeed {
+            seed: seed.trim().parse().ok()?,
+            start,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct GeneratorProgress {
+    /// Boards started so far, each of which is repaired until it is solved or
+    /// given up on.
+    pub attempts: usize,
+    /// Hidden cells next to opened ones on the board the solver last got stuck on.
+    pub frontier: usize,
+    pub elapsed: Duration,
+    /// The stuck board with the fewest hidden cells so far.
+    pub best: Option<Minefield>,
+}
+
+impl GeneratorProgress {
+    pub(crate) fn record_stuck(&mut self, minefield: &Minefield) {
+        self.frontier = frontier_size(minefield);
+
+        if self
+            .best
+            .as_ref()
+            .is_none_or(|best| minefield.total_hidden() < best.total_hidden())
+        {
+            self.best = Some(minefield.clone());
+        }
+    }
+}
 
 pub enum GeneratorStatus {
     Found(Minefield),
-    StillSolving(Option<Minefield>),
+    StillSolving(GeneratorProgress),
 }
 
-pub struct ParallelGuessfreeGenerator {
-    pub attempts: Arc<AtomicU32>,
-    pub found: Receiver<Minefield>,
-    pub stuck: Arc<Mutex<Option<Minefield>>>,
-    pub cancel: Sender<()>,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeneratorError {
+    Cancelled,
+    Disconnected,
 }
 
-impl ParallelGuessfreeGenerator {
-    pub fn new(
-        start: usize,
-        width: usize,
-        height: usize,
-        mines: usize,
-    ) -> ParallelGuessfreeGenerator {
-        let (tx, rx) = sync_channel(1);
-        let (cancel_tx, cancel_rx) = channel();
-
-        let attempts = Arc::new(AtomicU32::new(0));
-
-        let stuck = Arc::new(Mutex::new(None));
-
-        let generator = ParallelGuessfreeGenerator {
-            attempts: attempts.clone(),
-            found: rx,
-            stuck: stuck.clone(),
-            cancel: cancel_tx,
-        };
+impl Display for GeneratorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneratorError::Cancelled => write!(f, "generation was cancelled"),
+            GeneratorError::Disconnected => write!(f, "generator stopped unexpectedly"),
+        }
+    }
+}
 
-        thread::spawn(move || loop {
-            let mut minefield = Minefield::generate(&mut thread_rng(), width, height, mines);
-            attempts.fetch_add(1, Ordering::Relaxed);
+impl std::error::Error for GeneratorError {}
 
-            if minefield.cells[start].kind == CellKind::Mine {
-                continue;
+pub trait GuessfreeGenerator {
+    /// Checks on the generator, doing a slice of the work first if it runs on
+    /// the calling thread.
+    fn run(&mut self) -> Result<GeneratorStatus, GeneratorError>;
+
+    fn progress(&self) -> GeneratorProgress;
+
+    /// Stops the generator. Any later `run` returns `GeneratorError::Cancelled`.
+    fn cancel(&mut self);
+}
+
+/// Creates the generator backend suited to the current target.
+pub fn new_generator(
+    config: GeneratorConfig,
+) -> Result<Box<dyn GuessfreeGenerator>, PlacementError> {
+    // Every attempt places the same mines around the same first click, so
+    // the first tells whether any of them can.
+    Search::new(config, 0)?;
+
+    #[cfg(target_arch = "wasm32")]
+    let generator: Box<dyn GuessfreeGenerator> = match WorkerGuessfreeGenerator::new(config) {
+        Ok(generator) => Box::new(generator),
+        Err(err) => {
+            log::warn!("Couldn't start the generator worker, generating in frame: {err:?}");
+            Box::new(AsyncGuessfreeGenerator::new(config)?)
+        }
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    let generator = Box::new(ParallelGuessfreeGenerator::new(config));
+
+    Ok(generator)
+}
+
+/// Bounds on how hard a generated board may be, where `None` leaves that
+/// measure unconstrained. Both bounds are inclusive.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DifficultyBand {
+    pub three_bv: Option<(usize, usize)>,
+    /// How many deductions need the full constraint solver instead of a single
+    /// number, when solving from the first click.
+    pub advanced_steps: Option<(usize, usize)>,
+}
+
+impl DifficultyBand {
+    /// Carries the bounds over from a board of `old_cells` cells to one of
+    /// `new_cells`: maxima left at the whole old board follow it, and the rest
+    /// are kept within the new one.
+    pub fn fit(&mut self, old_cells: usize, new_cells: usize) {
+        for (min, max) in [&mut self.three_bv, &mut self.advanced_steps]
+            .into_iter()
+            .flatten()
+        {
+            if *max == old_cells {
+                *max = new_cells;
             }
 
-            minefield.open(start % width, start / width);
+            *max = (*max).min(new_cells);
+            *min = (*min).min(*max);
+        }
+    }
 
-            solve(&mut minefield);
+    pub fn contains(&self, minefield: &Minefield, start: usize) -> bool {
+        let within = |(min, max): (usize, usize), value: usize| min <= value && value <= max;
 
-            if cancel_rx.try_recv().is_ok() {
-                return;
+        if let Some(bounds) = self.three_bv {
+            if !within(bounds, minefield.three_bv()) {
+                return false;
             }
+        }
 
-            if minefield.is_solved() {
-                let _ = tx.send(minefield);
-                return;
+        if let Some(bounds) = self.advanced_steps {
+            if !within(bounds, solve_rated(&mut opened_at(minefield, start))) {
+                return false;
             }
+        }
+
+        true
+    }
+}
+
+/// Why `generate_in_band` couldn't start a game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BandError {
+    Placement(PlacementError),
+    /// No board within the band turned up after `MAX_BAND_ATTEMPTS`.
+    OutOfBand,
+}
+
+impl Display for BandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BandError::Placement(err) => err.fmt(f),
+            BandError::OutOfBand => write!(f, "no board found within the difficulty band"),
+        }
+    }
+}
+
+impl std::error::Error for BandError {}
 
+impl From<PlacementError> for BandError {
+    fn from(err: PlacementError) -> Self {
+        BandError::Placement(err)
+    }
+}
+
+/// Starts a game at `(x, y)` on a board within `band`, giving up after
+/// `MAX_BAND_ATTEMPTS`.
+pub fn generate_in_band(
+    rng: &mut impl Rng,
+    width: usize,
+    height: usize,
+    mines: usize,
+    (x, y): (usize, usize),
+    first_click: FirstClick,
+    band: DifficultyBand,
+) -> Result<Minefield, BandError> {
+    for _ in 0..MAX_BAND_ATTEMPTS {
+        let minefield = Minefield::start_at(rng, width, height, mines, (x, y), first_click)?;
+
+        if band.contains(&minefield, y * width + x) {
+            return Ok(minefield);
+        }
+    }
+
+    Err(BandError::OutOfBand)
+}
+
+fn initial_minefield(
+    rng: &mut impl Rng,
+    config: &GeneratorConfig,
+) -> Result<Minefield, PlacementError> {
+    // A board can only be solved without guessing from a safe first click.
+    let first_click = match config.first_click {
+        FirstClick::Unprotected => FirstClick::Safe,
+        first_click => first_click,
+    };
+
+    Minefield::start_at(
+        rng,
+        config.width,
+        config.height,
+        config.mines,
+        (config.start % config.width, config.start / config.width),
+        first_click,
+    )
+}
+
+fn frontier_size(minefield: &Minefield) -> usize {
+    (0..minefield.cells.len())
+        .filter(|&idx| {
+            minefield.cells[idx].state == CellState::Hidden
+                && minefield.neighboring_open(idx % minefield.width, idx / minefield.width)
+        })
+        .count()
+}
+
+fn opened_at(minefield: &Minefield, start: usize) -> Minefield {
+    let mut minefield = minefield.clone();
+    minefield.hide();
+    minefield.open(start % minefield.width, start / minefield.width);
+    minefield
+}
+
+/// Solves the layout of `minefield` again from the first click. Relocating
+/// mines can invalidate deductions made before the move, so a repaired board
+/// is only guess-free if the replay solves it too.
+fn replay(minefield: &Minefield, start: usize) -> Minefield {
+    let mut replayed = opened_at(minefield, start);
+    solve(&mut replayed);
+    replayed
+}
+
+/// Moves a mine the solver is stuck on to a hidden cell no opened cell
+/// constrains yet, so solving can resume from the current state. Returns
+/// false if there is no such pair of cells.
+pub fn relocate_frontier_mine(rng: &mut impl Rng, minefield: &mut Minefield) -> bool {
+    let width = minefield.width;
+
+    let undetermined = |kind: CellKind, frontier: bool| {
+        let minefield = &*minefield;
+        (0..minefield.cells.len()).filter(move |&idx| {
+            minefield.cells[idx].state == CellState::Hidden
+                && minefield.cells[idx].kind == kind
+                && minefield.neighboring_open(idx % width, idx / width) == frontier
+        })
+    };
+
+    let from = undetermined(CellKind::Mine, true).choose(rng);
+    let to = undetermined(CellKind::Empty, false).choose(rng);
+
+    let (Some(from), Some(to)) = (from, to) else {
+        return false;
+    };
+
+    minefield.cells[from].kind = CellKind::Empty;
+    minefield.cells[to].kind = CellKind::Mine;
+
+    // Opened neighbors of the moved mine may have become zeros, which would
+    // have been flood filled had they been opened with the new layout.
+    let zeros = minefield
+        .neighbors(from % width, from / width)
+        .filter(|&(x, y)| {
+            minefield.cells[y * width + x].state == CellState::Opened
+                && minefield.count_mines(x, y) == 0
+        })
+        .collect::<Vec<_>>();
+
+    for (x, y) in zeros {
+        let hidden = minefield
+            .neighbors(x, y)
+            .filter(|&(x, y)| minefield.cells[y * width + x].state == CellState::Hidden)
+            .collect::<Vec<_>>();
+
+        for (x, y) in hidden {
+            minefield.open(x, y);
+        }
+    }
+
+    true
+}
+
+/// Seeds the board for one attempt. Attempts are numbered, so the board
+/// found for a seed doesn't depend on how many workers search for it.
+fn attempt_seed(seed: u64, attempt: usize) -> u64 {
+    seed ^ (attempt as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+}
+
+pub(crate) enum SearchStep {
+    Solving,
+    /// The solver is stuck on `Search::minefield`, which the next step
+    /// repairs.
+    Stuck,
+    /// The attempt was given up on.
+    Failed,
+    Found(Minefield),
+}
+
+/// One attempt of the guess-free search shared by the generator backends,
+/// advanced one solver step at a time.
+pub(crate) struct Search {
+    pub(crate) config: GeneratorConfig,
+    rng: StdRng,
+    pub(crate) minefield: Minefield,
+    repairs: usize,
+    stuck: bool,
+}
+
+impl Search {
+    pub(crate) fn new(config: GeneratorConfig, attempt: usize) -> Result<Self, PlacementError> {
+        let mut rng = StdRng::seed_from_u64(attempt_seed(config.seed, attempt));
+        let minefield = initial_minefield(&mut rng, &config)?;
+
+        Ok(Search {
+            config,
+            rng,
+            minefield,
+            repairs: 0,
+            stuck: false,
+        })
+    }
+
+    pub(crate) fn step(&mut self) -> SearchStep {
+        let start = self.config.start;
+
+        if self.stuck {
+            self.stuck = false;
+
+            if self.repairs >= MAX_REPAIRS
+                || !relocate_frontier_mine(&mut self.rng, &mut self.minefield)
             {
-                let mut stuck = stuck.lock().unwrap();
-                *stuck = Some(minefield);
+                return SearchStep::Failed;
             }
-        });
 
-        generator
+            self.repairs += 1;
+
+            return SearchStep::Solving;
+        }
+
+        if solve_step(&mut self.minefield) {
+            return SearchStep::Solving;
+        }
+
+        if self.minefield.is_solved() {
+            let replayed = replay(&self.minefield, start);
+
+            if replayed.is_solved() {
+                if self.config.band.contains(&replayed, start) {
+                    return SearchStep::Found(opened_at(&replayed, start));
+                }
+
+                // Out of band boards are thrown away, as repairing only makes
+                // them more solvable and rarely moves them into the band.
+                return SearchStep::Failed;
+            }
+
+            self.minefield = replayed;
+        }
+
+        self.stuck = true;
+
+        SearchStep::Stuck
     }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+struct Shared {
+    config: GeneratorConfig,
+    next_attempt: AtomicUsize,
+    /// The attempts workers are on. Taken together with `next_attempt`, so an
+    /// attempt is listed as soon as it's numbered.
+    running: Mutex<BTreeSet<usize>>,
+    /// The lowest attempt that found a board, or `usize::MAX`.
+    found_attempt: AtomicUsize,
+    found: Mutex<Option<Minefield>>,
+    progress: Mutex<GeneratorProgress>,
+    cancelled: AtomicBool,
+}
 
-    pub fn attempts(&self) -> usize {
-        self.attempts.load(Ordering::Relaxed) as usize
+#[cfg(not(target_arch = "wasm32"))]
+impl Shared {
+    fn work(&self) {
+        loop {
+            let attempt = {
+                let mut running = self.running.lock().unwrap();
+                let attempt = self.next_attempt.fetch_add(1, Ordering::Relaxed);
+                running.insert(attempt);
+                attempt
+            };
+
+            let stop = self.search(attempt);
+            self.running.lock().unwrap().remove(&attempt);
+
+            if stop {
+                return;
+            }
+        }
     }
 
-    pub fn run(&mut self) -> GeneratorStatus {
-        match self.found.try_recv() {
-            Ok(minefield) => GeneratorStatus::Found(minefield),
-            Err(TryRecvError::Empty) => {
-                GeneratorStatus::StillSolving(self.stuck.lock().unwrap().clone())
+    /// Runs one attempt, returning whether the worker should stop.
+    fn search(&self, attempt: usize) -> bool {
+        // Leaves `run` to report the config as unusable.
+        let Ok(mut search) = Search::new(self.config, attempt) else {
+            return true;
+        };
+
+        loop {
+            // Only the lowest attempt that finds a board wins, so later
+            // attempts can stop as soon as an earlier one succeeds.
+            if self.cancelled.load(Ordering::Relaxed)
+                || self.found_attempt.load(Ordering::Relaxed) < attempt
+            {
+                return true;
+            }
+
+            match search.step() {
+                SearchStep::Solving => {}
+                SearchStep::Stuck => {
+                    let mut progress = self.progress.lock().unwrap();
+                    progress.record_stuck(&search.minefield);
+                }
+                SearchStep::Failed => return false,
+                SearchStep::Found(minefield) => {
+                    let mut found = self.found.lock().unwrap();
+
+                    if attempt < self.found_attempt.load(Ordering::Relaxed) {
+                        self.found_attempt.store(attempt, Ordering::Relaxed);
+                        *found = Some(minefield);
+                    }
+
+                    return false;
+                }
             }
-            Err(TryRecvError::Disconnected) => {
-                panic!("Generator thread disconnected")
+        }
+    }
+
+    /// Whether a board was found and no earlier attempt could still replace
+    /// it.
+    fn is_settled(&self) -> bool {
+        let found_attempt = self.found_attempt.load(Ordering::Relaxed);
+
+        found_attempt != usize::MAX
+            && self
+                .running
+                .lock()
+                .is_ok_and(|running| running.first().is_none_or(|&first| first >= found_attempt))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct ParallelGuessfreeGenerator {
+    started: Instant,
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ParallelGuessfreeGenerator {
+    pub fn new(config: GeneratorConfig) -> ParallelGuessfreeGenerator {
+        let workers = available_parallelism().map_or(1, NonZeroUsize::get);
+
+        Self::with_workers(config, workers)
+    }
+
+    pub fn with_workers(config: GeneratorConfig, workers: usize) -> ParallelGuessfreeGenerator {
+        let shared = Arc::new(Shared {
+            config,
+            next_attempt: AtomicUsize::new(0),
+            running: Mutex::new(BTreeSet::new()),
+            found_attempt: AtomicUsize::new(usize::MAX),
+            found: Mutex::new(None),
+            progress: Mutex::new(GeneratorProgress::default()),
+            cancelled: AtomicBool::new(false),
+        });
+
+        let workers = (0..workers.max(1))
+            .map(|_| {
+                let shared = shared.clone();
+                thread::spawn(move || shared.work())
+            })
+            .collect();
+
+        ParallelGuessfreeGenerator {
+            started: Instant::now(),
+            shared,
+            workers,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl GuessfreeGenerator for ParallelGuessfreeGenerator {
+    fn run(&mut self) -> Result<GeneratorStatus, GeneratorError> {
+        if self.shared.cancelled.load(Ordering::Relaxed) {
+            return Err(GeneratorError::Cancelled);
+        }
+
+        // The found board is final once no earlier attempt is left, and the
+        // workers on later ones stop on their own.
+        if self.shared.is_settled() {
+            if let Ok(Some(minefield)) = self.shared.found.lock().map(|mut found| found.take()) {
+                self.workers.clear();
+                return Ok(GeneratorStatus::Found(minefield));
             }
         }
+
+        // Otherwise every worker gave up, or one panicked.
+        if !self.workers.iter().all(JoinHandle::is_finished) {
+            return Ok(GeneratorStatus::StillSolving(self.progress()));
+        }
+
+        let panicked = self.workers.drain(..).any(|worker| worker.join().is_err());
+
+        match self.shared.found.lock().map(|mut found| found.take()) {
+            Ok(Some(minefield)) if !panicked => Ok(GeneratorStatus::Found(minefield)),
+            _ => Err(GeneratorError::Disconnected),
+        }
+    }
+
+    fn progress(&self) -> GeneratorProgress {
+        // A poisoned lock only means a worker panicked mid update, which `run`
+        // reports on its own.
+        let progress = self
+            .shared
+            .progress
+            .lock()
+            .map(|progress| progress.clone())
+            .unwrap_or_default();
+
+        GeneratorProgress {
+            attempts: self.shared.next_attempt.load(Ordering::Relaxed),
+            elapsed: self.started.elapsed(),
+            ..progress
+        }
+    }
+
+    fn cancel(&mut self) {
+        self.shared.cancelled.store(true, Ordering::Relaxed);
     }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl Drop for ParallelGuessfreeGenerator {
     fn drop(&mut self) {
-        let _ = self.cancel.send(());
+        self.cancel();
     }
 }
 
+#[cfg(target_arch = "wasm32")]
 pub struct AsyncGuessfreeGenerator {
-    start: usize,
-    mines: usize,
-    width: usize,
-    height: usize,
-    attempts: usize,
-    solving: Option<Minefield>,
+    started: Instant,
+    attempt: usize,
+    search: Search,
+    progress: GeneratorProgress,
+    cancelled: bool,
 }
 
+#[cfg(target_arch = "wasm32")]
 impl AsyncGuessfreeGenerator {
-    pub fn new(start: usize, width: usize, height: usize, mines: usize) -> Self {
-        AsyncGuessfreeGenerator {
-            start,
-            mines,
-            width,
-            height,
-            attempts: 0,
-            solving: Some(Minefield::new(width, height)),
+    pub fn new(config: GeneratorConfig) -> Result<Self, PlacementError> {
+        Ok(AsyncGuessfreeGenerator {
+            started: Instant::now(),
+            attempt: 0,
+            search: Search::new(config, 0)?,
+            progress: GeneratorProgress::default(),
+            cancelled: false,
+        })
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl GuessfreeGenerator for AsyncGuessfreeGenerator {
+    fn run(&mut self) -> Result<GeneratorStatus, GeneratorError> {
+        if self.cancelled {
+            return Err(GeneratorError::Cancelled);
+        }
+
+        let start_instant = Instant::now();
+
+        while start_instant.elapsed() < MIN_RENDER_INTERVAL {
+            match self.search.step() {
+                SearchStep::Solving => {}
+                SearchStep::Stuck => self.progress.record_stuck(&self.search.minefield),
+                SearchStep::Failed => {
+                    self.attempt += 1;
+                    self.search = Search::new(self.search.config, self.attempt)
+                        .map_err(|_| GeneratorError::Disconnected)?;
+                }
+                SearchStep::Found(minefield) => return Ok(GeneratorStatus::Found(minefield)),
+            }
         }
+
+        Ok(GeneratorStatus::StillSolving(self.progress()))
     }
 
-    pub fn attempts(&self) -> usize {
-        self.attempts
+    fn progress(&self) -> GeneratorProgress {
+        GeneratorProgress {
+            attempts: self.attempt + 1,
+            elapsed: self.started.elapsed(),
+            ..self.progress.clone()
+        }
     }
 
-    fn find_initial_minefield(&mut self) -> &mut Minefield {
-        loop {
-            self.attempts += 1;
+    fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+}
 
-            let mut minefield =
-                Minefield::generate(&mut thread_rng(), self.width, self.height, self.mines);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            if minefield.cells[self.start].kind != CellKind::Mine {
-                minefield.open(self.start % self.width, self.start / self.width);
-                self.solving = Some(minefield);
-                return self.solving.as_mut().unwrap();
-            }
+    #[test]
+    fn test_shared_seed() {
+        for shared in [
+            SharedSeed {
+                seed: 42,
+                start: None,
+            },
+            SharedSeed {
+                seed: u64::MAX,
+                start: Some((3, 14)),
+            },
+        ] {
+            assert_eq!(SharedSeed::decode(&shared.encode()), Some(shared));
         }
+
+        assert_eq!(
+            SharedSeed::decode(" 7 @ 1, 2 "),
+            Some(SharedSeed {
+                seed: 7,
+                start: Some((1, 2))
+            })
+        );
+        assert_eq!(SharedSeed::decode("7@1"), None);
+        assert_eq!(SharedSeed::decode("seven"), None);
     }
 
-    pub fn run(&mut self) -> GeneratorStatus {
-        let start_instant = Instant::now();
+    #[test]
+    fn test_relocate_frontier_mine() {
+        let mut minefield = Minefield::parse(
+            r#"01m..
+               01...
+               1m...
+               ....m"#,
+        );
+        let mines = minefield.total_mines();
 
-        let mut minefield = match self.solving {
-            Some(ref mut minefield) => minefield,
-            None => self.find_initial_minefield(),
-        };
+        assert!(relocate_frontier_mine(
+            &mut StdRng::seed_from_u64(0),
+            &mut minefield
+        ));
 
-        while start_instant.elapsed() < MIN_RENDER_INTERVAL {
-            let changed = solve_step(minefield);
+        assert_eq!(minefield.total_mines(), mines);
+        assert!(!minefield.is_lost());
+    }
+
+    fn config() -> GeneratorConfig {
+        GeneratorConfig {
+            start: 14,
+            width: 6,
+            height: 6,
+            mines: 5,
+            seed: 0,
+            first_click: FirstClick::Opening,
+            band: DifficultyBand::default(),
+        }
+    }
+
+    #[test]
+    fn test_guessfree_generator() {
+        let mut generator = ParallelGuessfreeGenerator::new(config());
 
-            if minefield.is_solved() {
-                let mut solved_minefield = minefield.clone();
-                solved_minefield.hide();
-                solved_minefield.open(self.start % self.width, self.start / self.width);
-                return GeneratorStatus::Found(solved_minefield);
+        let minefield = loop {
+            if let GeneratorStatus::Found(minefield) = generator.run().unwrap() {
+                break minefield;
             }
 
-            if !changed {
-                minefield = self.find_initial_minefield();
+            thread::sleep(std::time::Duration::from_millis(10));
+        };
+
+        assert_eq!(minefield.total_mines(), 5);
+        assert_eq!(minefield.cells[14].state, CellState::Opened);
+        assert_eq!(minefield.count_mines(14 % 6, 14 / 6), 0);
+        assert!(replay(&minefield, 14).is_solved());
+    }
+
+    #[test]
+    fn test_generator_is_independent_of_workers() {
+        let generate = |workers| {
+            let mut generator = ParallelGuessfreeGenerator::with_workers(
+                GeneratorConfig {
+                    first_click: FirstClick::Safe,
+                    ..config()
+                },
+                workers,
+            );
+
+            loop {
+                if let GeneratorStatus::Found(minefield) = generator.run().unwrap() {
+                    return minefield;
+                }
+
+                thread::sleep(std::time::Duration::from_millis(10));
             }
+        };
+
+        assert_eq!(generate(1), generate(4));
+    }
+
+    #[test]
+    fn test_cancel_generator() {
+        let mut generator = ParallelGuessfreeGenerator::new(GeneratorConfig {
+            width: 30,
+            height: 16,
+            mines: 99,
+            ..config()
+        });
+
+        generator.cancel();
+
+        assert_eq!(generator.run().err(), Some(GeneratorError::Cancelled));
+    }
+
+    #[test]
+    fn test_generate_in_band() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let band = DifficultyBand {
+            three_bv: Some((20, 25)),
+            advanced_steps: None,
+        };
+
+        for _ in 0..10 {
+            let minefield =
+                generate_in_band(&mut rng, 9, 9, 10, (4, 4), FirstClick::Safe, band).unwrap();
+
+            assert!((20..=25).contains(&minefield.three_bv()));
         }
 
-        GeneratorStatus::StillSolving(Some(minefield.clone()))
+        let band = DifficultyBand {
+            three_bv: Some((80, 81)),
+            advanced_steps: None,
+        };
+
+        assert_eq!(
+            generate_in_band(&mut rng, 9, 9, 10, (4, 4), FirstClick::Safe, band),
+            Err(BandError::OutOfBand)
+        );
+    }
+
+    #[test]
+    fn test_fit_band() {
+        let mut band = DifficultyBand {
+            three_bv: Some((10, 81)),
+            advanced_steps: Some((0, 50)),
+        };
+
+        band.fit(81, 480);
+        assert_eq!(band.three_bv, Some((10, 480)));
+        assert_eq!(band.advanced_steps, Some((0, 50)));
+
+        band.fit(480, 20);
+        assert_eq!(band.three_bv, Some((10, 20)));
+        assert_eq!(band.advanced_steps, Some((0, 20)));
     }
 }
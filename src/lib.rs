@@ -1,11 +1,18 @@
 pub mod board;
 pub mod canvas;
+pub mod daily;
+pub mod drills;
 mod generating;
 pub mod minefield;
 pub mod minesweeper;
 pub mod ms_button;
 pub mod ms_frame;
 pub mod ms_modal;
+pub mod puzzles;
 mod rref;
+pub mod skin;
 pub mod solver;
+pub mod theme;
+pub mod tutorial;
 mod utils;
+pub mod worker;
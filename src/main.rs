@@ -1,20 +1,8 @@
-mod board;
-mod canvas;
-mod generating;
-mod minefield;
-mod minesweeper;
-mod ms_button;
-mod ms_frame;
-mod ms_modal;
-mod rref;
-mod solver;
-mod utils;
-
-use minesweeper::Minesweeper;
+use minesweeper::minesweeper::Minesweeper;
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> Result<(), eframe::Error> {
-    use minefield::Minefield;
+    use minesweeper::minefield::Minefield;
 
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
@@ -22,6 +10,7 @@ fn main() -> Result<(), eframe::Error> {
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default(),
+        follow_system_theme: true,
         ..Default::default()
     };
 
@@ -48,7 +37,7 @@ F200002F201."#,
             // This gives us image support:
             egui_extras::install_image_loaders(&cc.egui_ctx);
 
-            Box::new(Minesweeper::new_beginner(&cc.egui_ctx))
+            Box::new(Minesweeper::new_beginner(&cc.egui_ctx).with_storage(cc.storage))
             // Box::new(Minesweeper::start_from_minefield(&cc.egui_ctx, minefield))
         }),
     )
@@ -68,7 +57,7 @@ fn main() {
                 Box::new(|cc| {
                     egui_extras::install_image_loaders(&cc.egui_ctx);
 
-                    Box::new(Minesweeper::new(&cc.egui_ctx))
+                    Box::new(Minesweeper::new_beginner(&cc.egui_ctx).with_storage(cc.storage))
                 }),
             )
             .await
@@ -1,6 +1,8 @@
 use std::fmt::{Debug, Display};
 
-use rand::{seq::IteratorRandom, Rng};
+use std::iter::once;
+
+use rand::Rng;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum CellKind {
@@ -13,6 +15,48 @@ pub enum CellState {
     Hidden,
     Opened,
     Flagged,
+    /// Marked with a question mark. Only a note for the player: it is hidden
+    /// in every other way, and never counts as a flag.
+    Questioned,
+}
+
+impl CellState {
+    /// Whether the cell is hidden, with or without a question mark.
+    pub fn is_hidden(self) -> bool {
+        matches!(self, CellState::Hidden | CellState::Questioned)
+    }
+}
+
+/// More mines were asked for than there are cells to place them in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlacementError {
+    pub mines: usize,
+    pub available: usize,
+}
+
+impl Display for PlacementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "can't place {} mines in {} cells",
+            self.mines, self.available
+        )
+    }
+}
+
+impl std::error::Error for PlacementError {}
+
+/// What the first click of a game is guaranteed to hit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FirstClick {
+    /// The clicked cell is never a mine.
+    #[default]
+    Safe,
+    /// The clicked cell is a zero, so it always opens an area. Falls back to
+    /// `Safe` when the board is too dense to keep its neighbors clear.
+    Opening,
+    /// The clicked cell can be a mine.
+    Unprotected,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -42,8 +86,40 @@ impl Minefield {
         }
     }
 
-    pub fn generate(rng: &mut impl Rng, width: usize, height: usize, mines: usize) -> Self {
-        assert!(mines < width * height);
+    pub fn generate(
+        rng: &mut impl Rng,
+        width: usize,
+        height: usize,
+        mines: usize,
+    ) -> Result<Self, PlacementError> {
+        Minefield::generate_excluding(rng, width, height, mines, &[])
+    }
+
+    /// Places `mines` uniformly over the cells not in `excluded`, by shuffling
+    /// just the first `mines` of the candidate cells into place.
+    pub fn generate_excluding(
+        rng: &mut impl Rng,
+        width: usize,
+        height: usize,
+        mines: usize,
+        excluded: &[usize],
+    ) -> Result<Self, PlacementError> {
+        let mut is_excluded = vec![false; width * height];
+
+        for &idx in excluded {
+            is_excluded[idx] = true;
+        }
+
+        let mut candidates = (0..width * height)
+            .filter(|&idx| !is_excluded[idx])
+            .collect::<Vec<_>>();
+
+        if mines > candidates.len() {
+            return Err(PlacementError {
+                mines,
+                available: candidates.len(),
+            });
+        }
 
         let mut cells = (0..width * height)
             .map(|_| Cell {
@@ -52,36 +128,71 @@ impl Minefield {
             })
             .collect::<Vec<_>>();
 
-        for _ in 0..mines {
-            loop {
-                let x = rng.gen_range(0..width);
-                let y = rng.gen_range(0..height);
-                let cell = &mut cells[y * width + x];
-                if cell.kind == CellKind::Empty {
-                    cell.kind = CellKind::Mine;
-                    break;
-                }
-            }
+        for i in 0..mines {
+            let j = rng.gen_range(i as u64..candidates.len() as u64) as usize;
+            candidates.swap(i, j);
+            cells[candidates[i]].kind = CellKind::Mine;
         }
 
-        Minefield {
+        Ok(Minefield {
             cells,
             width,
             height,
+        })
+    }
+
+    pub fn generate_protected(
+        rng: &mut impl Rng,
+        width: usize,
+        height: usize,
+        mines: usize,
+        (x, y): (usize, usize),
+        first_click: FirstClick,
+    ) -> Result<Self, PlacementError> {
+        let mut protected = match first_click {
+            FirstClick::Unprotected => Vec::new(),
+            FirstClick::Safe => vec![y * width + x],
+            FirstClick::Opening => once((x, y))
+                .chain(Minefield::new(width, height).neighbors(x, y))
+                .map(|(x, y)| y * width + x)
+                .collect(),
+        };
+
+        if width * height - protected.len() < mines {
+            protected.truncate(1);
         }
+
+        Minefield::generate_excluding(rng, width, height, mines, &protected)
     }
 
-    pub fn random_start(rng: &mut impl Rng, width: usize, height: usize, mines: usize) -> Self {
-        let mut minefield = Minefield::generate(rng, width, height, mines);
+    pub fn start_at(
+        rng: &mut impl Rng,
+        width: usize,
+        height: usize,
+        mines: usize,
+        (x, y): (usize, usize),
+        first_click: FirstClick,
+    ) -> Result<Self, PlacementError> {
+        let mut minefield =
+            Minefield::generate_protected(rng, width, height, mines, (x, y), first_click)?;
+
+        minefield.open(x, y);
 
-        let start = (0..width * height)
-            .filter(|&i| minefield.cells[i].kind == CellKind::Empty)
-            .choose(rng)
-            .unwrap();
+        Ok(minefield)
+    }
+
+    pub fn random_start(
+        rng: &mut impl Rng,
+        width: usize,
+        height: usize,
+        mines: usize,
+    ) -> Result<Self, PlacementError> {
+        let start = rng.gen_range(0..(width * height) as u64) as usize;
+        let mut minefield = Minefield::generate_excluding(rng, width, height, mines, &[start])?;
 
         minefield.open(start % width, start / width);
 
-        minefield
+        Ok(minefield)
     }
 
     pub fn new(width: usize, height: usize) -> Self {
@@ -113,7 +224,7 @@ impl Minefield {
         }
 
         for (x, y) in self.neighbors(x, y) {
-            if self.cells[y * self.width + x].state == CellState::Hidden {
+            if self.cells[y * self.width + x].state.is_hidden() {
                 self.open(x, y);
             }
         }
@@ -136,7 +247,7 @@ impl Minefield {
     pub fn total_hidden(&self) -> usize {
         self.cells
             .iter()
-            .filter(|cell| cell.state == CellState::Hidden)
+            .filter(|cell| cell.state.is_hidden())
             .count()
     }
 
@@ -159,7 +270,7 @@ impl Minefield {
 
     pub fn count_hidden(&self, x: usize, y: usize) -> usize {
         self.neighbors(x, y)
-            .filter(|(x, y)| self.cells[*y * self.width + *x].state == CellState::Hidden)
+            .filter(|(x, y)| self.cells[*y * self.width + *x].state.is_hidden())
             .count()
     }
 
@@ -196,6 +307,8 @@ impl Minefield {
                     (CellState::Opened, CellKind::Mine) => 'M',
                     (CellState::Flagged, CellKind::Empty) => 'f',
                     (CellState::Flagged, CellKind::Mine) => 'F',
+                    (CellState::Questioned, CellKind::Empty) => 'q',
+                    (CellState::Questioned, CellKind::Mine) => 'Q',
                 };
                 s.push(c);
             }
@@ -236,6 +349,14 @@ impl Minefield {
                     kind: CellKind::Mine,
                     state: CellState::Flagged,
                 },
+                'q' => Cell {
+                    kind: CellKind::Empty,
+                    state: CellState::Questioned,
+                },
+                'Q' => Cell {
+                    kind: CellKind::Mine,
+                    state: CellState::Questioned,
+                },
                 '\n' => {
                     height += 1;
                     continue;
@@ -254,6 +375,50 @@ impl Minefield {
         }
     }
 
+    /// The minimum number of clicks needed to clear the board: one per
+    /// opening plus one per empty cell that no opening reveals.
+    pub fn three_bv(&self) -> usize {
+        let mut revealed = vec![false; self.cells.len()];
+        let mut clicks = 0;
+
+        for idx in 0..self.cells.len() {
+            let (x, y) = (idx % self.width, idx / self.width);
+
+            if revealed[idx]
+                || self.cells[idx].kind == CellKind::Mine
+                || self.count_mines(x, y) != 0
+            {
+                continue;
+            }
+
+            clicks += 1;
+            revealed[idx] = true;
+
+            let mut stack = vec![(x, y)];
+
+            while let Some((x, y)) = stack.pop() {
+                for (nx, ny) in self.neighbors(x, y) {
+                    let nidx = ny * self.width + nx;
+
+                    if revealed[nidx] {
+                        continue;
+                    }
+
+                    revealed[nidx] = true;
+
+                    if self.count_mines(nx, ny) == 0 {
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+        }
+
+        clicks
+            + (0..self.cells.len())
+                .filter(|&idx| !revealed[idx] && self.cells[idx].kind == CellKind::Empty)
+                .count()
+    }
+
     pub fn is_solved(&self) -> bool {
         self.cells.iter().all(|cell| {
             (cell.kind == CellKind::Empty && cell.state == CellState::Opened)
@@ -264,7 +429,7 @@ impl Minefield {
     pub fn is_lost(&self) -> bool {
         self.cells
             .iter()
-            .any(|cell| (cell.kind == CellKind::Mine && cell.state == CellState::Opened))
+            .any(|cell| cell.kind == CellKind::Mine && cell.state == CellState::Opened)
     }
 
     pub fn hide(&mut self) {
@@ -272,6 +437,20 @@ impl Minefield {
             cell.state = CellState::Hidden;
         }
     }
+
+    /// The board with every flag taken back, for reasoning that shouldn't
+    /// trust the player's flags.
+    pub fn without_flags(&self) -> Self {
+        let mut minefield = self.clone();
+
+        for cell in minefield.cells.iter_mut() {
+            if cell.state == CellState::Flagged {
+                cell.state = CellState::Hidden;
+            }
+        }
+
+        minefield
+    }
 }
 
 impl Display for Minefield {
@@ -288,6 +467,8 @@ impl Debug for Minefield {
 
 #[cfg(test)]
 mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
     use super::*;
 
     #[test]
@@ -301,4 +482,113 @@ mod tests {
 
         assert!(!minefield.neighboring_open(3, 1));
     }
+
+    #[test]
+    fn test_random_start_is_reproducible() {
+        let a = Minefield::random_start(&mut StdRng::seed_from_u64(42), 16, 16, 40).unwrap();
+        let b = Minefield::random_start(&mut StdRng::seed_from_u64(42), 16, 16, 40).unwrap();
+        let c = Minefield::random_start(&mut StdRng::seed_from_u64(43), 16, 16, 40).unwrap();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_generate_is_pinned() {
+        // Pinned so that seeds, and with them daily boards, match across
+        // platforms and releases.
+        let minefield = Minefield::generate(&mut StdRng::seed_from_u64(0), 9, 9, 10).unwrap();
+
+        assert_eq!(
+            minefield,
+            Minefield::parse(
+                "...m.....\n\
+                 .......mm\n\
+                 .....m...\n\
+                 .........\n\
+                 .........\n\
+                 ...m.....\n\
+                 .....m..m\n\
+                 m.m......\n\
+                 ......m..\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_first_click_opening() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..100 {
+            let minefield =
+                Minefield::start_at(&mut rng, 9, 9, 10, (4, 0), FirstClick::Opening).unwrap();
+
+            assert_eq!(minefield.cells[4].kind, CellKind::Empty);
+            assert_eq!(minefield.count_mines(4, 0), 0);
+            assert_eq!(minefield.total_mines(), 10);
+        }
+
+        // Too dense to clear the neighbors, but the clicked cell stays safe.
+        let minefield =
+            Minefield::start_at(&mut rng, 3, 3, 7, (1, 1), FirstClick::Opening).unwrap();
+
+        assert_eq!(minefield.cells[4].state, CellState::Opened);
+        assert!(!minefield.is_lost());
+        assert_eq!(minefield.total_mines(), 7);
+    }
+
+    #[test]
+    fn test_generate_dense() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let minefield = Minefield::generate(&mut rng, 30, 16, 480).unwrap();
+        assert_eq!(minefield.total_mines(), 480);
+
+        let minefield =
+            Minefield::start_at(&mut rng, 30, 16, 479, (3, 5), FirstClick::Safe).unwrap();
+        assert_eq!(minefield.total_mines(), 479);
+        assert!(!minefield.is_lost());
+
+        assert_eq!(
+            Minefield::generate(&mut rng, 3, 3, 10),
+            Err(PlacementError {
+                mines: 10,
+                available: 9
+            })
+        );
+        assert_eq!(
+            Minefield::start_at(&mut rng, 3, 3, 9, (1, 1), FirstClick::Opening),
+            Err(PlacementError {
+                mines: 9,
+                available: 8
+            })
+        );
+    }
+
+    #[test]
+    fn test_three_bv() {
+        let minefield = Minefield::parse(
+            r#"...m
+               ....
+               m...
+               ...."#,
+        );
+
+        // Two openings, plus the bottom left cell that neither of them reaches.
+        assert_eq!(minefield.three_bv(), 3);
+    }
+
+    #[test]
+    fn test_question_marks() {
+        let minefield = Minefield::parse("qQF\n.m.\n...");
+
+        assert_eq!(Minefield::parse(&minefield.format()), minefield);
+        assert_eq!(minefield.count_flags(1, 1), 1);
+        assert_eq!(minefield.total_hidden(), 8);
+
+        // Question marks don't stop an opening from spreading.
+        let mut minefield = Minefield::parse("..q\n...\n..m");
+        minefield.open(0, 0);
+        assert_eq!(minefield.format(), "000\n011\n01m\n");
+    }
 }
@@ -1,28 +1,36 @@
-use rand::thread_rng;
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
 use web_time::Instant;
 
-use crate::canvas::Canvas;
-use crate::generating::{AsyncGuessfreeGenerator, GeneratorStatus, ParallelGuessfreeGenerator};
+use crate::canvas::{Canvas, Zoom, ZOOM_KEY};
+use crate::daily::{daily_config, date, today, DailyRecord, DAILY_RECORD_KEY};
+use crate::drills::{generate_drill, Drill, DrillStats, DRILL_STATS_KEY, PATTERNS};
+use crate::generating::{
+    generate_in_band, new_generator, DifficultyBand, GeneratorConfig, GeneratorProgress,
+    GeneratorStatus, GuessfreeGenerator, SharedSeed,
+};
 use crate::ms_button::MinesweeperButton;
 use crate::ms_frame::MinesweeperFrame;
 use crate::ms_modal::MinesweeperModal;
-use crate::solver::solve_step;
-use crate::utils::load_image;
+use crate::puzzles::{available_packs, Pack, PuzzleProgress, PUZZLE_PROGRESS_KEY};
+use crate::skin::{available_skins, Role, Skin, SKIN_KEY};
+use crate::solver::{find_hint, solve};
+use crate::theme::{Theme, ThemePreference, THEME_KEY};
+use crate::tutorial::{is_right_move, Tutorial, LESSONS};
 use crate::{
-    board::Board,
-    minefield::{CellKind, CellState, Minefield},
+    board::{Board, PlaySettings, TouchSettings, TOUCH_SETTINGS_KEY},
+    minefield::{CellKind, CellState, FirstClick, Minefield},
+};
+use eframe::egui::{Sense, Ui, Widget};
+use egui::{
+    pos2, vec2, Align, DragValue, Frame, Label, Layout, Margin, Rect, Response, TextEdit, Vec2,
 };
-use eframe::egui::{Image, Sense, Ui, Widget};
-use egui::{include_image, Align, Color32, Frame, Label, Layout, Margin, Response, Vec2, Visuals};
-use log::info;
+use log::{info, warn};
+use std::{fmt::Display, sync::Arc};
 
 const DIGITS_IN_COUNTERS: usize = 3;
 const FACE_SIZE: f32 = 24.0;
-
-#[cfg(target_arch = "wasm32")]
-type Generator = AsyncGuessfreeGenerator;
-#[cfg(not(target_arch = "wasm32"))]
-type Generator = ParallelGuessfreeGenerator;
+/// A guess at `Minesweeper::chrome` for the first frame.
+const DEFAULT_CHROME: Vec2 = vec2(24.0, 64.0);
 
 pub struct Minesweeper {
     pub board: Board,
@@ -33,45 +41,43 @@ pub struct Minesweeper {
     pub started: bool,
     pub last_pressed: Option<(usize, usize, Instant)>,
     pub menu_open: bool,
-    guessfree_generator: Option<Generator>,
-    pub digits: [Image<'static>; 10],
-    pub margin_corners: [Image<'static>; 2],
-    pub faces: [Image<'static>; 5],
+    pub seed: u64,
+    /// The cell the game with `seed` was started from, shared with it since
+    /// the board is laid out around it.
+    pub seed_start: Option<(usize, usize)>,
+    seed_input: String,
+    pub guess_free: bool,
+    /// How the player set up play, applied to every board.
+    pub settings: PlaySettings,
+    pub first_click: FirstClick,
+    pub band: DifficultyBand,
+    guessfree_generator: Option<Box<dyn GuessfreeGenerator>>,
+    /// The day whose daily challenge is being played, if any.
+    pub daily: Option<u64>,
+    pub daily_record: DailyRecord,
+    pub packs: Vec<Pack>,
+    /// The pack and level being played, if any.
+    pub puzzle: Option<(usize, usize)>,
+    pub puzzle_progress: PuzzleProgress,
+    pub puzzles_open: bool,
+    /// The last layout checked in the editor, and whether it was solvable.
+    editor_check: Option<(Minefield, bool)>,
+    pub tutorial: Option<Tutorial>,
+    pub drill: Option<Drill>,
+    pub drill_stats: DrillStats,
+    /// Why the last game couldn't be started, shown until one is.
+    pub error: Option<String>,
+    pub theme: ThemePreference,
+    pub zoom: Zoom,
+    /// The size of everything around the board, in points, as laid out last
+    /// frame.
+    chrome: Vec2,
+    /// The skins to pick from, and the one picked. `None` follows the theme.
+    pub skins: Vec<Arc<Skin>>,
+    pub skin: Option<usize>,
 }
 
 impl Minesweeper {
-    fn load_digits() -> [Image<'static>; 10] {
-        [
-            load_image(include_image!("../assets/d-0.png")),
-            load_image(include_image!("../assets/d-1.png")),
-            load_image(include_image!("../assets/d-2.png")),
-            load_image(include_image!("../assets/d-3.png")),
-            load_image(include_image!("../assets/d-4.png")),
-            load_image(include_image!("../assets/d-5.png")),
-            load_image(include_image!("../assets/d-6.png")),
-            load_image(include_image!("../assets/d-7.png")),
-            load_image(include_image!("../assets/d-8.png")),
-            load_image(include_image!("../assets/d-9.png")),
-        ]
-    }
-
-    fn load_margin_corners() -> [Image<'static>; 2] {
-        [
-            load_image(include_image!("../assets/margin-corner-2.png")),
-            load_image(include_image!("../assets/margin-corner-3.png")),
-        ]
-    }
-
-    fn load_faces() -> [Image<'static>; 5] {
-        [
-            load_image(include_image!("../assets/face.png")),
-            load_image(include_image!("../assets/face-pressed.png")),
-            load_image(include_image!("../assets/face-pressing.png")),
-            load_image(include_image!("../assets/face-won.png")),
-            load_image(include_image!("../assets/face-lost.png")),
-        ]
-    }
-
     fn from_minefield(minefield: Minefield, mines: usize) -> Self {
         Minesweeper {
             board: Board::from_minefield(minefield),
@@ -82,10 +88,30 @@ impl Minesweeper {
             started: true,
             last_pressed: None,
             menu_open: false,
+            seed: thread_rng().gen(),
+            seed_start: None,
+            seed_input: String::new(),
+            guess_free: false,
+            settings: PlaySettings::default(),
+            theme: ThemePreference::default(),
+            zoom: Zoom::default(),
+            chrome: DEFAULT_CHROME,
+            first_click: FirstClick::default(),
+            band: DifficultyBand::default(),
             guessfree_generator: None,
-            digits: Self::load_digits(),
-            margin_corners: Self::load_margin_corners(),
-            faces: Self::load_faces(),
+            daily: None,
+            daily_record: DailyRecord::default(),
+            packs: available_packs(),
+            puzzle: None,
+            puzzle_progress: PuzzleProgress::default(),
+            puzzles_open: false,
+            editor_check: None,
+            tutorial: None,
+            drill: None,
+            drill_stats: DrillStats::default(),
+            error: None,
+            skins: vec![Arc::new(Skin::builtin())],
+            skin: None,
         }
     }
 
@@ -96,27 +122,93 @@ impl Minesweeper {
             canvas: Canvas::new(),
             start: Instant::now(),
             finished: None,
-            started: true,
+            started: false,
             last_pressed: None,
             menu_open: false,
+            seed: thread_rng().gen(),
+            seed_start: None,
+            seed_input: String::new(),
+            guess_free: false,
+            settings: PlaySettings::default(),
+            theme: ThemePreference::default(),
+            zoom: Zoom::default(),
+            chrome: DEFAULT_CHROME,
+            first_click: FirstClick::default(),
+            band: DifficultyBand::default(),
             guessfree_generator: None,
-            digits: Self::load_digits(),
-            margin_corners: Self::load_margin_corners(),
-            faces: Self::load_faces(),
+            daily: None,
+            daily_record: DailyRecord::default(),
+            packs: available_packs(),
+            puzzle: None,
+            puzzle_progress: PuzzleProgress::default(),
+            puzzles_open: false,
+            editor_check: None,
+            tutorial: None,
+            drill: None,
+            drill_stats: DrillStats::default(),
+            error: None,
+            skins: vec![Arc::new(Skin::builtin())],
+            skin: None,
         }
     }
 
     fn setup(ctx: &egui::Context) {
         Self::setup_fonts(ctx);
-        let mut visuals = Visuals::light();
-        visuals.override_text_color = Some(Color32::BLACK);
-
-        ctx.set_visuals(visuals);
+        ctx.set_visuals(Theme::current(ctx).visuals());
     }
 
     pub fn new_beginner(ctx: &egui::Context) -> Self {
         Self::setup(ctx);
-        Self::from_size_and_mines(9, 9, 10)
+        Self {
+            skins: available_skins(ctx),
+            ..Self::from_size_and_mines(9, 9, 10)
+        }
+    }
+
+    pub fn with_storage(mut self, storage: Option<&dyn eframe::Storage>) -> Self {
+        if let Some(record) = storage
+            .and_then(|storage| storage.get_string(DAILY_RECORD_KEY))
+            .and_then(|record| DailyRecord::decode(&record))
+        {
+            self.daily_record = record;
+        }
+
+        if let Some(progress) = storage.and_then(|storage| storage.get_string(PUZZLE_PROGRESS_KEY))
+        {
+            self.puzzle_progress = PuzzleProgress::decode(&progress);
+        }
+
+        if let Some(stats) = storage.and_then(|storage| storage.get_string(DRILL_STATS_KEY)) {
+            self.drill_stats = DrillStats::decode(&stats);
+        }
+
+        if let Some(touch) = storage
+            .and_then(|storage| storage.get_string(TOUCH_SETTINGS_KEY))
+            .and_then(|touch| TouchSettings::decode(&touch))
+        {
+            self.settings.touch = touch;
+            self.apply_settings();
+        }
+
+        if let Some(zoom) = storage
+            .and_then(|storage| storage.get_string(ZOOM_KEY))
+            .and_then(|zoom| Zoom::decode(&zoom))
+        {
+            self.zoom = zoom;
+        }
+
+        if let Some(theme) = storage
+            .and_then(|storage| storage.get_string(THEME_KEY))
+            .and_then(|theme| ThemePreference::decode(&theme))
+        {
+            self.theme = theme;
+        }
+
+        self.skin = storage
+            .and_then(|storage| storage.get_string(SKIN_KEY))
+            .and_then(|name| self.skins.iter().position(|skin| skin.name == name));
+
+        self
     }
 
     pub fn start_from_minefield(ctx: &egui::Context, minefield: Minefield) -> Self {
@@ -127,7 +219,10 @@ impl Minesweeper {
             .filter(|cell| cell.kind == CellKind::Mine)
             .count();
 
-        Self::from_minefield(minefield, mines)
+        Self {
+            skins: available_skins(ctx),
+            ..Self::from_minefield(minefield, mines)
+        }
     }
 
     pub fn counter(&self, ui: &mut Ui, number: usize) -> Response {
@@ -139,7 +234,7 @@ impl Minesweeper {
 
                         for i in (0..DIGITS_IN_COUNTERS).rev() {
                             let digit: usize = (number / 10_usize.pow(i as u32)) % 10;
-                            ui.add(self.digits[digit].clone());
+                            ui.add(Skin::current(ui.ctx()).image(Role::Digit(digit)));
                         }
                     })
                     .response;
@@ -169,7 +264,7 @@ impl Minesweeper {
             .minefield
             .cells
             .iter()
-            .filter(|cell| cell.kind == CellKind::Mine && cell.state == CellState::Hidden)
+            .filter(|cell| cell.kind == CellKind::Mine && cell.state.is_hidden())
             .count();
         self.counter(ui, mines)
     }
@@ -182,18 +277,18 @@ impl Minesweeper {
                     ui.allocate_exact_size(Vec2::splat(FACE_SIZE), Sense::click());
 
                 let face = if response.is_pointer_button_down_on() {
-                    &self.faces[1]
+                    Role::FacePressed
                 } else if self.board.minefield.is_solved() {
-                    &self.faces[3]
+                    Role::FaceWon
                 } else if self.board.minefield.is_lost() {
-                    &self.faces[4]
+                    Role::FaceLost
                 } else if self.board.pressed.is_some() {
-                    &self.faces[2]
+                    Role::FacePressing
                 } else {
-                    &self.faces[0]
+                    Role::Face
                 };
 
-                face.paint_at(ui, rect);
+                Skin::current(ui.ctx()).image(face).paint_at(ui, rect);
 
                 response
             })
@@ -219,6 +314,34 @@ impl Minesweeper {
                         self.menu_open = true;
                     }
 
+                    if MinesweeperButton::new()
+                        .show(ui, |ui| {
+                            ui.add(Label::new("?").selectable(false));
+                        })
+                        .response
+                        .clicked()
+                    {
+                        self.hint();
+                    }
+
+                    // Swaps what a tap does, shown as the action it does now.
+                    if MinesweeperButton::new()
+                        .show(ui, |ui| {
+                            ui.add(Skin::current(ui.ctx()).image(
+                                if self.settings.touch.tap_flags {
+                                    Role::Flag
+                                } else {
+                                    Role::Empty
+                                },
+                            ));
+                        })
+                        .response
+                        .clicked()
+                    {
+                        self.settings.touch.tap_flags = !self.settings.touch.tap_flags;
+                        self.apply_settings();
+                    }
+
                     ui.add_space(ui.available_width() - counter_size);
 
                     self.mine_counter(ui)
@@ -228,6 +351,48 @@ impl Minesweeper {
             .inner
     }
 
+    fn pick_size(&mut self, width: usize, height: usize, mines: usize) {
+        let cells = self.board.minefield.width * self.board.minefield.height;
+
+        self.band.fit(cells, width * height);
+        self.set_board(Minefield::new(width, height));
+        self.mines = mines;
+        self.reset();
+    }
+
+    /// A toggle between any value and a range, and the range's bounds.
+    fn band_bounds(ui: &mut Ui, name: &str, bounds: &mut Option<(usize, usize)>, limit: usize) {
+        ui.horizontal(|ui| {
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(
+                        Label::new(if bounds.is_some() {
+                            format!("{name}:")
+                        } else {
+                            format!("{name}: Any")
+                        })
+                        .selectable(false),
+                    );
+                })
+                .response
+                .clicked()
+            {
+                *bounds = match bounds {
+                    Some(_) => None,
+                    None => Some((0, limit)),
+                };
+            }
+
+            if let Some((min, max)) = bounds {
+                let (lower, upper) = (*min, *max);
+
+                ui.add(DragValue::new(min).clamp_range(0..=upper));
+                ui.label("-");
+                ui.add(DragValue::new(max).clamp_range(lower..=limit));
+            }
+        });
+    }
+
     fn menu(&mut self, ui: &mut Ui) {
         ui.with_layout(Layout::top_down(Align::Center), |ui| {
             if MinesweeperButton::new()
@@ -237,9 +402,7 @@ impl Minesweeper {
                 .response
                 .clicked()
             {
-                self.board = Board::from_minefield(Minefield::new(9, 9));
-                self.mines = 10;
-                self.reset();
+                self.pick_size(9, 9, 10);
                 self.menu_open = false;
             }
             if MinesweeperButton::new()
@@ -249,9 +412,7 @@ impl Minesweeper {
                 .response
                 .clicked()
             {
-                self.board = Board::from_minefield(Minefield::new(16, 16));
-                self.mines = 40;
-                self.reset();
+                self.pick_size(16, 16, 40);
                 self.menu_open = false;
             }
             if MinesweeperButton::new()
@@ -261,17 +422,512 @@ impl Minesweeper {
                 .response
                 .clicked()
             {
-                self.board = Board::from_minefield(Minefield::new(30, 16));
-                self.mines = 99;
+                self.pick_size(30, 16, 99);
+                self.menu_open = false;
+            }
+
+            let today = today();
+            let (year, month, day) = date(today);
+            let daily = match self.daily_record.best_on(today) {
+                Some(best) => format!(
+                    "Daily {year}-{month:02}-{day:02}: {:.1}s",
+                    best.as_secs_f32()
+                ),
+                None => format!("Daily {year}-{month:02}-{day:02}"),
+            };
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(Label::new(daily).selectable(false));
+                })
+                .response
+                .clicked()
+            {
+                self.start_daily(today);
+                self.menu_open = false;
+            }
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(Label::new("Puzzles").selectable(false));
+                })
+                .response
+                .clicked()
+            {
+                self.puzzles_open = true;
+                self.menu_open = false;
+            }
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(Label::new("Editor").selectable(false));
+                })
+                .response
+                .clicked()
+            {
+                self.guessfree_generator = None;
                 self.reset();
+                self.board.editing = true;
+                self.menu_open = false;
+            }
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(Label::new("Tutorial").selectable(false));
+                })
+                .response
+                .clicked()
+            {
+                self.start_lesson(0);
                 self.menu_open = false;
             }
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(Label::new("Drills").selectable(false));
+                })
+                .response
+                .clicked()
+            {
+                self.start_drill();
+                self.menu_open = false;
+            }
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(
+                        Label::new(if self.guess_free {
+                            "No guessing: On"
+                        } else {
+                            "No guessing: Off"
+                        })
+                        .selectable(false),
+                    );
+                })
+                .response
+                .clicked()
+            {
+                self.guess_free = !self.guess_free;
+            }
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(
+                        Label::new(if self.settings.adversarial {
+                            "Adversarial: On"
+                        } else {
+                            "Adversarial: Off"
+                        })
+                        .selectable(false),
+                    );
+                })
+                .response
+                .clicked()
+            {
+                self.settings.adversarial = !self.settings.adversarial;
+                self.apply_settings();
+            }
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(
+                        Label::new(if self.settings.question_marks {
+                            "Question marks: On"
+                        } else {
+                            "Question marks: Off"
+                        })
+                        .selectable(false),
+                    );
+                })
+                .response
+                .clicked()
+            {
+                self.settings.question_marks = !self.settings.question_marks;
+                self.apply_settings();
+            }
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(
+                        Label::new(if self.settings.practice {
+                            "Practice: On"
+                        } else {
+                            "Practice: Off"
+                        })
+                        .selectable(false),
+                    );
+                })
+                .response
+                .clicked()
+            {
+                self.settings.practice = !self.settings.practice;
+                self.apply_settings();
+            }
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(
+                        Label::new(if self.settings.heatmap {
+                            "Heatmap: On"
+                        } else {
+                            "Heatmap: Off"
+                        })
+                        .selectable(false),
+                    );
+                })
+                .response
+                .clicked()
+            {
+                self.settings.heatmap = !self.settings.heatmap;
+                self.apply_settings();
+            }
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(
+                        Label::new(match self.first_click {
+                            FirstClick::Safe => "First click: Safe",
+                            FirstClick::Opening => "First click: Opening",
+                            FirstClick::Unprotected => "First click: Unprotected",
+                        })
+                        .selectable(false),
+                    );
+                })
+                .response
+                .clicked()
+            {
+                self.first_click = match self.first_click {
+                    FirstClick::Safe => FirstClick::Opening,
+                    FirstClick::Opening => FirstClick::Unprotected,
+                    FirstClick::Unprotected => FirstClick::Safe,
+                };
+            }
+
+            let cells = self.board.minefield.width * self.board.minefield.height;
+
+            Self::band_bounds(ui, "3BV", &mut self.band.three_bv, cells);
+            Self::band_bounds(ui, "Hard steps", &mut self.band.advanced_steps, cells);
+
+            ui.horizontal(|ui| {
+                ui.label("Long press:");
+
+                if ui
+                    .add(
+                        DragValue::new(&mut self.settings.touch.long_press)
+                            .clamp_range(TouchSettings::LONG_PRESS_RANGE)
+                            .speed(0.01)
+                            .suffix("s"),
+                    )
+                    .changed()
+                {
+                    self.apply_settings();
+                }
+            });
+
+            ui.horizontal(|ui| {
+                if MinesweeperButton::new()
+                    .show(ui, |ui| {
+                        ui.add(
+                            Label::new(match self.zoom {
+                                Zoom::Fit => "Zoom: Fit board".to_string(),
+                                Zoom::Fixed(scale) => format!("Zoom: {scale}x"),
+                            })
+                            .selectable(false),
+                        );
+                    })
+                    .response
+                    .clicked()
+                {
+                    self.zoom = self.zoom.next();
+                }
+
+                if MinesweeperButton::new()
+                    .show(ui, |ui| {
+                        ui.add(
+                            Label::new(format!("Theme: {}", self.theme.encode())).selectable(false),
+                        );
+                    })
+                    .response
+                    .clicked()
+                {
+                    self.theme = self.theme.next();
+                }
+
+                if MinesweeperButton::new()
+                    .show(ui, |ui| {
+                        ui.add(
+                            Label::new(match self.skin {
+                                Some(skin) => format!("Skin: {}", self.skins[skin].name),
+                                None => "Skin: Theme".to_string(),
+                            })
+                            .selectable(false),
+                        );
+                    })
+                    .response
+                    .clicked()
+                {
+                    self.skin = match self.skin {
+                        None => Some(0),
+                        Some(skin) if skin + 1 < self.skins.len() => Some(skin + 1),
+                        Some(_) => None,
+                    };
+                }
+            });
+
+            ui.add_space(8.0);
+            let shared = SharedSeed {
+                seed: self.seed,
+                start: self.seed_start,
+            };
+            ui.label(format!("Seed: {}", shared.encode()));
+
+            ui.horizontal(|ui| {
+                ui.add(
+                    TextEdit::singleline(&mut self.seed_input)
+                        .hint_text("Seed")
+                        .desired_width(80.0),
+                );
+
+                let shared = SharedSeed::decode(&self.seed_input);
+
+                if MinesweeperButton::new()
+                    .show(ui, |ui| {
+                        ui.add(Label::new("Play seed").selectable(false));
+                    })
+                    .response
+                    .clicked()
+                {
+                    if let Some(shared) = shared {
+                        self.reset_with_seed(shared.seed);
+                        self.menu_open = false;
+
+                        let (width, height) =
+                            (self.board.minefield.width, self.board.minefield.height);
+
+                        if let Some(start) = shared.start.filter(|(x, y)| *x < width && *y < height)
+                        {
+                            self.open_first(start);
+                        }
+                    }
+                }
+            });
         })
         .inner
     }
 
-    fn generator_status(ui: &mut Ui, generator: &Generator) -> bool {
-        ui.label(format!("Attempts: {}", generator.attempts()));
+    fn puzzle_list(&mut self, ui: &mut Ui) {
+        ui.with_layout(Layout::top_down(Align::Center), |ui| {
+            let mut selected = None;
+
+            for (pack_idx, pack) in self.packs.iter().enumerate() {
+                ui.label(&pack.title);
+
+                for (level_idx, level) in pack.levels.iter().enumerate() {
+                    let completed = if self.puzzle_progress.is_completed(pack, level_idx) {
+                        "[x]"
+                    } else {
+                        "[ ]"
+                    };
+
+                    if MinesweeperButton::new()
+                        .show(ui, |ui| {
+                            ui.add(
+                                Label::new(format!(
+                                    "{completed} {} ({})",
+                                    level.title, level.difficulty
+                                ))
+                                .selectable(false),
+                            );
+                        })
+                        .response
+                        .clicked()
+                    {
+                        selected = Some((pack_idx, level_idx));
+                    }
+                }
+
+                ui.add_space(8.0);
+            }
+
+            if let Some((pack, level)) = selected {
+                self.start_puzzle(pack, level);
+                self.puzzles_open = false;
+            }
+        });
+    }
+
+    fn editor(&mut self, ui: &mut Ui) {
+        let solvable = match &self.editor_check {
+            Some((minefield, solvable)) if *minefield == self.board.minefield => *solvable,
+            _ => {
+                let mut solved = self.board.minefield.clone();
+                solve(&mut solved);
+
+                let solvable = solved.is_solved() && !solved.is_lost();
+                self.editor_check = Some((self.board.minefield.clone(), solvable));
+                solvable
+            }
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(if solvable {
+                "Solvable from here: Yes"
+            } else {
+                "Solvable from here: No"
+            });
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(Label::new("Copy").selectable(false));
+                })
+                .response
+                .clicked()
+            {
+                let text = self.board.minefield.format();
+                info!("Edited minefield: \n{}", text);
+                ui.output_mut(|output| output.copied_text = text);
+            }
+
+            if MinesweeperButton::new()
+                .show(ui, |ui| {
+                    ui.add(Label::new("Play").selectable(false));
+                })
+                .response
+                .clicked()
+            {
+                let minefield = self.board.minefield.clone();
+                self.mines = minefield.total_mines();
+                self.start(minefield);
+            }
+        });
+    }
+
+    fn lesson(&mut self, ui: &mut Ui) {
+        let Some(tutorial) = self.tutorial else {
+            return;
+        };
+        let lesson = &LESSONS[tutorial.lesson];
+
+        ui.vertical(|ui| {
+            ui.set_max_width(self.board.size().0);
+
+            ui.label(format!(
+                "Lesson {}/{}: {}",
+                tutorial.lesson + 1,
+                LESSONS.len(),
+                lesson.title
+            ));
+
+            match tutorial.right {
+                None => ui.label(lesson.prompt),
+                Some(false) => ui.label(format!("Not quite. {}", lesson.prompt)),
+                Some(true) => ui.label(format!("Right! {}", lesson.reason)),
+            };
+
+            if tutorial.right == Some(true)
+                && MinesweeperButton::new()
+                    .show(ui, |ui| {
+                        ui.add(
+                            Label::new(if tutorial.lesson + 1 < LESSONS.len() {
+                                "Next"
+                            } else {
+                                "Done"
+                            })
+                            .selectable(false),
+                        );
+                    })
+                    .response
+                    .clicked()
+            {
+                if tutorial.lesson + 1 < LESSONS.len() {
+                    self.start_lesson(tutorial.lesson + 1);
+                } else {
+                    self.reset();
+                }
+            }
+        });
+    }
+
+    fn drill_panel(&mut self, ui: &mut Ui) {
+        let Some(drill) = self.drill else {
+            return;
+        };
+
+        ui.vertical(|ui| match drill.right {
+            None => {
+                ui.label("Open a safe cell or flag a mine.");
+            }
+            Some(right) => {
+                ui.label(format!(
+                    "{} {}",
+                    if right { "Right!" } else { "Wrong." },
+                    PATTERNS[drill.pattern].name
+                ));
+
+                for (pattern, stats) in PATTERNS.iter().zip(&self.drill_stats.patterns) {
+                    if let (Some(accuracy), Some(time)) = (stats.accuracy(), stats.average_time()) {
+                        ui.label(format!(
+                            "{}: {:.0}% {:.1}s",
+                            pattern.name,
+                            accuracy * 100.0,
+                            time.as_secs_f32()
+                        ));
+                    }
+                }
+
+                if MinesweeperButton::new()
+                    .show(ui, |ui| {
+                        ui.add(Label::new("Next").selectable(false));
+                    })
+                    .response
+                    .clicked()
+                {
+                    self.start_drill();
+                }
+            }
+        });
+    }
+
+    /// Points out a move the solver can prove, without making it.
+    fn hint(&mut self) {
+        let minefield = &self.board.minefield;
+
+        if !self.started || minefield.is_lost() || minefield.is_solved() {
+            return;
+        }
+
+        match find_hint(minefield) {
+            Some(hint) => {
+                self.board.hint = Some((hint, Instant::now()));
+                self.board.assisted = true;
+            }
+            None => info!("No move can be proven, a guess is needed"),
+        }
+    }
+
+    /// Checks a move made in the tutorial or a drill, undoing it if wrong.
+    fn judge_move(&mut self, before: Minefield) {
+        let right = is_right_move(&before, &self.board.minefield);
+
+        if let Some(tutorial) = &mut self.tutorial {
+            tutorial.right = Some(right);
+        }
+
+        if let Some(drill) = &mut self.drill {
+            drill.right = Some(right);
+            self.drill_stats
+                .record(drill.pattern, right, drill.shown.elapsed());
+        }
+
+        if !right {
+            self.board.take_back();
+        }
+    }
+
+    fn generator_status(ui: &mut Ui, progress: &GeneratorProgress) -> bool {
+        ui.label(format!("Attempts: {}", progress.attempts));
+        ui.label(format!("Frontier: {}", progress.frontier));
+        ui.label(format!("Time: {:.1}s", progress.elapsed.as_secs_f32()));
         if MinesweeperButton::new()
             .show(ui, |ui| {
                 ui.label("Cancel");
@@ -286,40 +942,175 @@ impl Minesweeper {
     }
 
     fn reset(&mut self) {
+        self.reset_with_seed(thread_rng().gen());
+    }
+
+    fn reset_with_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.seed_start = None;
         self.canvas = Canvas::new();
-        self.board = Board::from_minefield(Minefield::new(
+        self.started = false;
+        self.finished = None;
+        self.daily = None;
+        self.puzzle = None;
+        self.tutorial = None;
+        self.drill = None;
+        self.set_board(Minefield::new(
             self.board.minefield.width,
             self.board.minefield.height,
         ));
-        self.started = false;
-        self.finished = None;
     }
 
-    fn start_generating(&mut self, start: usize) {
+    fn start_drill(&mut self) {
+        let mut rng = thread_rng();
+        let pattern = self.drill_stats.next_pattern(&mut rng);
+
+        let Some(minefield) = generate_drill(&mut rng, &PATTERNS[pattern]) else {
+            warn!("Couldn't generate a drill for {}", PATTERNS[pattern].name);
+            return;
+        };
+
+        self.guessfree_generator = None;
+        self.reset();
+        self.mines = minefield.total_mines();
+        self.start(minefield);
+        self.drill = Some(Drill {
+            pattern,
+            shown: Instant::now(),
+            right: None,
+        });
+    }
+
+    fn start_lesson(&mut self, lesson: usize) {
+        let minefield = LESSONS[lesson].minefield();
+
+        self.guessfree_generator = None;
+        self.reset();
+        self.mines = minefield.total_mines();
+        self.start(minefield);
+        self.tutorial = Some(Tutorial {
+            lesson,
+            right: None,
+        });
+    }
+
+    fn start_puzzle(&mut self, pack: usize, level: usize) {
+        let minefield = self.packs[pack].levels[level].minefield.clone();
+        let mines = self.packs[pack].levels[level].mines();
+
+        self.guessfree_generator = None;
+        self.reset();
+        self.mines = mines;
+        self.start(minefield);
+        self.puzzle = Some((pack, level));
+    }
+
+    /// Sets up the daily board and generates it right away, as its first
+    /// click is part of the puzzle.
+    fn start_daily(&mut self, day: u64) {
+        let config = daily_config(day);
+
+        self.set_board(Minefield::new(config.width, config.height));
+        self.mines = config.mines;
+        self.reset_with_seed(config.seed);
+        self.daily = Some(day);
+        self.start_generating(config);
+    }
+
+    fn generator_config(&self, start: usize) -> GeneratorConfig {
+        GeneratorConfig {
+            start,
+            width: self.board.minefield.width,
+            height: self.board.minefield.height,
+            mines: self.mines,
+            seed: self.seed,
+            first_click: self.first_click,
+            band: self.band,
+        }
+    }
+
+    fn start_generating(&mut self, config: GeneratorConfig) {
+        let generator = match new_generator(config) {
+            Ok(generator) => generator,
+            Err(err) => {
+                self.fail_start(err);
+                return;
+            }
+        };
+
+        self.error = None;
+
         let mut minefield = Minefield::new(self.board.minefield.width, self.board.minefield.height);
 
-        minefield.cells[start].state = CellState::Opened;
+        minefield.cells[config.start].state = CellState::Opened;
+
+        self.set_board(minefield);
+
+        self.guessfree_generator = Some(generator);
+    }
+
+    /// Lays out the board around the first opened cell and starts the game,
+    /// or the guess-free generator.
+    fn open_first(&mut self, (x, y): (usize, usize)) {
+        self.seed_start = Some((x, y));
 
+        if self.guess_free {
+            self.start_generating(self.generator_config(y * self.board.minefield.width + x));
+        } else {
+            match generate_in_band(
+                &mut StdRng::seed_from_u64(self.seed),
+                self.board.minefield.width,
+                self.board.minefield.height,
+                self.mines,
+                (x, y),
+                self.first_click,
+                self.band,
+            ) {
+                Ok(minefield) => self.start(minefield),
+                Err(err) => self.fail_start(err),
+            }
+        }
+    }
+
+    /// Replaces the board, which takes on the player's settings.
+    fn set_board(&mut self, minefield: Minefield) {
         self.board = Board::from_minefield(minefield);
+        self.apply_settings();
+    }
 
-        self.guessfree_generator = Some(Generator::new(
-            start,
-            self.board.minefield.width,
-            self.board.minefield.height,
-            self.mines,
-        ));
+    fn apply_settings(&mut self) {
+        self.board.settings = PlaySettings {
+            // The daily is the same puzzle for everyone, which redrawing the
+            // layout would undo.
+            adversarial: self.settings.adversarial && self.daily.is_none(),
+            ..self.settings
+        };
     }
 
     fn start(&mut self, minefield: Minefield) {
-        self.board = Board::from_minefield(minefield);
+        self.set_board(minefield);
         self.start = Instant::now();
         self.started = true;
+        self.error = None;
         info!(
-            "Started game with minefield: \n{}",
+            "Started game with seed {} and minefield: \n{}",
+            self.seed,
             self.board.minefield.format()
         );
     }
 
+    /// Reports why a game couldn't be started, and clears the cells the
+    /// click opened, so the player can change the settings and try again.
+    fn fail_start(&mut self, err: impl Display) {
+        warn!("Couldn't start a game: {}", err);
+        self.error = Some(format!("Couldn't start a game: {err}."));
+        self.seed_start = None;
+        self.set_board(Minefield::new(
+            self.board.minefield.width,
+            self.board.minefield.height,
+        ));
+    }
+
     fn setup_fonts(ctx: &egui::Context) {
         // Start with the default fonts (we will be adding to them rather than replacing them).
         let mut fonts = egui::FontDefinitions::default();
@@ -354,43 +1145,103 @@ impl Minesweeper {
 
 impl Widget for &mut Minesweeper {
     fn ui(self, ui: &mut Ui) -> Response {
-        let response = MinesweeperFrame::new(3)
+        let cursor = self.board.cursor;
+
+        let skin = self.skin.unwrap_or_else(|| {
+            let name = Theme::current(ui.ctx()).skin;
+            self.skins
+                .iter()
+                .position(|skin| skin.name == name)
+                .unwrap_or_default()
+        });
+
+        Skin::set_current(ui.ctx(), self.skins[skin].clone());
+
+        let frame = MinesweeperFrame::new(3)
             .floating()
             .margin(Margin::same(6.0))
             .show(ui, |ui| {
                 self.header(ui);
 
-                MinesweeperFrame::new(3)
+                // Once answered, the rest of the board can be played freely.
+                let judging = self
+                    .tutorial
+                    .is_some_and(|tutorial| tutorial.right != Some(true))
+                    || self.drill.is_some_and(|drill| drill.right.is_none());
+                let before = judging.then(|| self.board.minefield.clone());
+
+                let (response, board_area) = MinesweeperFrame::new(3)
                     .show(ui, |ui| {
-                        // ui.add(&mut self.board)
-                        self.canvas
-                            .show(ui, self.board.size().into(), |ui| ui.add(&mut self.board))
-                            .inner
+                        let canvas = self
+                            .canvas
+                            .show(ui, self.board.size().into(), |ui| ui.add(&mut self.board));
+
+                        (canvas.inner, canvas.response.rect)
                     })
-                    .inner
-            })
-            .inner;
-
-        if !self.started && response.clicked() {
-            if let Some((x, y, _)) = self.last_pressed {
-                self.start(Minefield::random_start(
-                    &mut thread_rng(),
-                    self.board.minefield.width,
-                    self.board.minefield.height,
-                    self.mines,
-                ));
-                // self.start_generating(y * self.board.minefield.width + x);
+                    .inner;
+
+                if let Some((x, y)) = self.board.cursor.filter(|_| self.board.cursor != cursor) {
+                    self.canvas.keep_visible(
+                        self.board.size().into(),
+                        Rect::from_min_size(pos2(x as f32, y as f32) * 16.0, vec2(16.0, 16.0)),
+                    );
+                }
+
+                if self.board.editing {
+                    self.editor(ui);
+                }
+
+                if let Some(before) = before.filter(|before| *before != self.board.minefield) {
+                    self.judge_move(before);
+                }
+
+                self.lesson(ui);
+                self.drill_panel(ui);
+
+                if self.board.assisted {
+                    ui.label("Assisted: not counted for records.");
+                }
+
+                if let Some(error) = &self.error {
+                    ui.label(error);
+                }
+
+                (response, board_area)
+            });
+
+        let (response, board_area) = frame.inner;
+        self.chrome = frame.response.rect.size() - board_area.size();
+
+        let first_open = match self.last_pressed {
+            Some((x, y, _)) if response.clicked() => Some((x, y)),
+            _ => self.board.key_opened.take(),
+        };
+
+        if !self.started && !self.board.editing {
+            if let Some(start) = first_open {
+                self.open_first(start);
             }
         }
 
         self.last_pressed = self.board.pressed;
 
         if self.board.minefield.is_solved() && self.finished.is_none() {
-            self.finished = Some(Instant::now());
-        }
+            let finished = Instant::now();
+            self.finished = Some(finished);
 
-        if ui.input(|i| i.key_pressed(egui::Key::Space)) {
-            solve_step(&mut self.board.minefield);
+            // Undoing mistakes or seeing the odds doesn't count towards records.
+            if !self.board.assisted {
+                if let Some(day) = self.daily.filter(|_| !self.board.settings.adversarial) {
+                    self.daily_record.record(day, finished - self.start);
+                }
+
+                if let Some((pack, level)) = self.puzzle {
+                    self.puzzle_progress.complete(&self.packs[pack], level);
+                }
+            }
+        } else if !self.board.minefield.is_solved() && self.finished.is_some() {
+            // An undo in practice took the win back, so the clock runs again.
+            self.finished = None;
         }
 
         let mut menu_modal = MinesweeperModal::new(self.menu_open);
@@ -401,25 +1252,40 @@ impl Widget for &mut Minesweeper {
 
         self.menu_open = menu_modal.open && self.menu_open;
 
+        let mut puzzles_modal = MinesweeperModal::new(self.puzzles_open);
+
+        puzzles_modal.show(ui, |ui| {
+            self.puzzle_list(ui);
+        });
+
+        self.puzzles_open = puzzles_modal.open && self.puzzles_open;
+
         let mut cancel = false;
 
         if let Some(generator) = &mut self.guessfree_generator {
             match generator.run() {
-                GeneratorStatus::Found(minefield) => {
+                Ok(GeneratorStatus::Found(minefield)) => {
                     self.start(minefield);
                     self.guessfree_generator = None;
                 }
-                GeneratorStatus::StillSolving(Some(minefield)) => {
-                    self.board = Board::from_minefield(minefield);
+                Ok(GeneratorStatus::StillSolving(progress)) => {
+                    if let Some(minefield) = progress.best {
+                        self.set_board(minefield);
+                    }
+                }
+                Err(err) => {
+                    warn!("Guess-free generation stopped: {}", err);
+                    cancel = true;
                 }
-                _ => {}
             }
         }
 
         if let Some(generator) = &mut self.guessfree_generator {
+            let progress = generator.progress();
+
             if MinesweeperModal::new(true)
                 .show(ui, |ui| {
-                    if Minesweeper::generator_status(ui, generator) {
+                    if Minesweeper::generator_status(ui, &progress) {
                         cancel = true;
                     }
                 })
@@ -430,9 +1296,23 @@ impl Widget for &mut Minesweeper {
         }
 
         if cancel {
+            if let Some(generator) = &mut self.guessfree_generator {
+                generator.cancel();
+            }
+
             self.guessfree_generator = None;
+            self.daily = None;
             self.board.minefield =
                 Minefield::new(self.board.minefield.width, self.board.minefield.height);
+            self.board.changed();
+        }
+
+        // New games and generator progress replace the board, but the cursor
+        // should stay where it was.
+        if self.board.cursor.is_none() {
+            self.board.cursor = cursor.filter(|(x, y)| {
+                *x < self.board.minefield.width && *y < self.board.minefield.height
+            });
         }
 
         response
@@ -440,8 +1320,11 @@ impl Widget for &mut Minesweeper {
 }
 
 impl eframe::App for Minesweeper {
-    fn update(&mut self, ctx: &eframe::egui::Context, _: &mut eframe::Frame) {
-        ctx.set_pixels_per_point(4.0);
+    fn update(&mut self, ctx: &eframe::egui::Context, frame: &mut eframe::Frame) {
+        Theme::set_current(ctx, self.theme.resolve(frame.info().system_theme));
+        let screen = ctx.input(|i| i.screen_rect.size()) * ctx.pixels_per_point();
+        let size = Vec2::from(self.board.size()) + self.chrome;
+        ctx.set_pixels_per_point(self.zoom.scale(size, screen));
         ctx.tessellation_options_mut(|opts| {
             opts.feathering = false;
         });
@@ -452,4 +1335,19 @@ impl eframe::App for Minesweeper {
                 self.ui(ui);
             });
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(DAILY_RECORD_KEY, self.daily_record.encode());
+        storage.set_string(PUZZLE_PROGRESS_KEY, self.puzzle_progress.encode());
+        storage.set_string(DRILL_STATS_KEY, self.drill_stats.encode());
+        storage.set_string(TOUCH_SETTINGS_KEY, self.settings.touch.encode());
+        storage.set_string(ZOOM_KEY, self.zoom.encode());
+        storage.set_string(THEME_KEY, self.theme.encode());
+        storage.set_string(
+            SKIN_KEY,
+            self.skin
+                .map(|skin| self.skins[skin].name.clone())
+                .unwrap_or_default(),
+        );
+    }
 }
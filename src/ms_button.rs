@@ -2,6 +2,7 @@ use egui::{emath::TSTransform, InnerResponse, LayerId, Margin, Order, Sense, Sha
 
 use crate::ms_frame::MinesweeperFrame;
 
+#[derive(Default)]
 pub struct MinesweeperButton;
 
 impl MinesweeperButton {
@@ -1,18 +1,8 @@
-use crate::utils::load_image;
-use egui::{
-    include_image, pos2, Color32, Context, Image, InnerResponse, Margin, Rect, Sense, Shape, Ui,
-    Vec2,
+use crate::{
+    skin::{Role, Skin},
+    theme::Theme,
 };
-use once_cell::sync::Lazy;
-
-pub const SHADOW_COLOR: Color32 = Color32::from_rgb(128, 128, 128);
-const HIGHTLIGHT_COLOR: Color32 = Color32::WHITE;
-const BACKGROUND_COLOR: Color32 = Color32::from_rgb(192, 192, 192);
-
-const MARGIN_CORNER_2: Lazy<Image<'static>> =
-    Lazy::new(|| load_image(include_image!("../assets/margin-corner-2.png")));
-const MARGIN_CORNER_3: Lazy<Image<'static>> =
-    Lazy::new(|| load_image(include_image!("../assets/margin-corner-3.png")));
+use egui::{pos2, Color32, Context, InnerResponse, Margin, Rect, Sense, Shape, Ui};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FrameKind {
@@ -95,10 +85,11 @@ impl MinesweeperFrame {
         let b = self.border_margin();
         let inner_rect = self.inner_rect(content_rect);
         let outer_rect = self.outer_rect(content_rect);
+        let theme = Theme::current(ctx);
 
         let mut shapes = Vec::new();
 
-        shapes.push(Shape::rect_filled(inner_rect, 0.0, BACKGROUND_COLOR));
+        shapes.push(Shape::rect_filled(inner_rect, 0.0, theme.background));
         // left
         shapes.push(Shape::rect_filled(
             Rect::from_min_max(
@@ -115,9 +106,9 @@ impl MinesweeperFrame {
             ),
             0.0,
             if self.kind == FrameKind::Floating {
-                HIGHTLIGHT_COLOR
+                theme.highlight
             } else {
-                SHADOW_COLOR
+                theme.shadow
             },
         ));
         // top
@@ -136,9 +127,9 @@ impl MinesweeperFrame {
             ),
             0.0,
             if self.kind == FrameKind::Floating {
-                HIGHTLIGHT_COLOR
+                theme.highlight
             } else {
-                SHADOW_COLOR
+                theme.shadow
             },
         ));
         // right
@@ -150,9 +141,9 @@ impl MinesweeperFrame {
                 ),
                 0.0,
                 if self.kind == FrameKind::Inscribed {
-                    HIGHTLIGHT_COLOR
+                    theme.highlight
                 } else {
-                    SHADOW_COLOR
+                    theme.shadow
                 },
             ));
             // bottom
@@ -163,49 +154,47 @@ impl MinesweeperFrame {
                 ),
                 0.0,
                 if self.kind == FrameKind::Inscribed {
-                    HIGHTLIGHT_COLOR
+                    theme.highlight
                 } else {
-                    SHADOW_COLOR
+                    theme.shadow
                 },
             ));
         }
 
         if self.border > 1 && self.kind != FrameKind::Pressed {
-            let corner = if self.border == 3 {
-                MARGIN_CORNER_3
-            } else if self.border == 2 {
-                MARGIN_CORNER_2
-            } else {
+            if self.border > 3 {
                 panic!("Border too large: {:?}", self.border);
-            };
+            }
 
-            let uv = if self.kind == FrameKind::Floating {
-                Rect::from_min_max(pos2(1.0, 1.0), pos2(0.0, 0.0))
-            } else {
-                Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0))
-            };
+            let skin = Skin::current(ctx);
 
-            let corner = corner.load_for_size(ctx, Vec2::splat(16.0)).unwrap();
-
-            shapes.push(Shape::image(
-                corner.texture_id().unwrap(),
-                Rect::from_min_max(
-                    pos2(outer_rect.max.x - b.right, outer_rect.min.y),
-                    pos2(outer_rect.max.x, outer_rect.min.y + b.top),
-                ),
-                uv,
-                Color32::WHITE,
-            ));
-
-            shapes.push(Shape::image(
-                corner.texture_id().unwrap(),
-                Rect::from_min_max(
-                    pos2(outer_rect.min.x, outer_rect.max.y - b.bottom),
-                    pos2(outer_rect.min.x + b.left, outer_rect.max.y),
-                ),
-                uv,
-                Color32::WHITE,
-            ));
+            if let Some((texture, uv)) = skin.sprite(Role::Corner(self.border)).texture(ctx) {
+                let uv = if self.kind == FrameKind::Floating {
+                    Rect::from_min_max(uv.max, uv.min)
+                } else {
+                    uv
+                };
+
+                shapes.push(Shape::image(
+                    texture,
+                    Rect::from_min_max(
+                        pos2(outer_rect.max.x - b.right, outer_rect.min.y),
+                        pos2(outer_rect.max.x, outer_rect.min.y + b.top),
+                    ),
+                    uv,
+                    Color32::WHITE,
+                ));
+
+                shapes.push(Shape::image(
+                    texture,
+                    Rect::from_min_max(
+                        pos2(outer_rect.min.x, outer_rect.max.y - b.bottom),
+                        pos2(outer_rect.min.x + b.left, outer_rect.max.y),
+                    ),
+                    uv,
+                    Color32::WHITE,
+                ));
+            }
         }
 
         Shape::Vec(shapes)
@@ -1,8 +1,6 @@
-use egui::{Align2, Area, Frame, Margin, Pos2, Response, Rounding, Sense, Ui, Window};
+use egui::{Align2, Frame, Margin, Response, Rounding, Sense, Ui, Window};
 
-use crate::ms_frame::MinesweeperFrame;
-
-const OVERLAY_COLOR: egui::Color32 = egui::Color32::from_black_alpha(100);
+use crate::{ms_frame::MinesweeperFrame, theme::Theme};
 
 pub struct MinesweeperModal {
     pub open: bool,
@@ -29,8 +27,11 @@ impl MinesweeperModal {
             self.open = false;
         }
 
-        ui.painter()
-            .rect_filled(screen_rect, Rounding::ZERO, OVERLAY_COLOR);
+        ui.painter().rect_filled(
+            screen_rect,
+            Rounding::ZERO,
+            Theme::current(ui.ctx()).overlay,
+        );
 
         let window = Window::new("")
             .id("modal_window".into())
@@ -41,7 +42,7 @@ impl MinesweeperModal {
             .anchor(Align2::CENTER_CENTER, [0., 0.])
             .resizable(false);
 
-        window.show(&ui.ctx(), |ui| {
+        window.show(ui.ctx(), |ui| {
             MinesweeperFrame::new(3)
                 .margin(Margin::same(10.0))
                 .floating()
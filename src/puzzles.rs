@@ -0,0 +1,340 @@
+//! Puzzle packs: partially opened boards that can be finished without
+//! guessing.
+//!
+//! A pack file starts with a `pack:` line, followed by levels separated by
+//! blank lines. Each level has a `level:` and a `difficulty:` line, then its
+//! board in the `Minefield::format` style. Flags may only sit on mines.
+
+use crate::{
+    minefield::{CellKind, CellState, Minefield},
+    solver::solve,
+};
+use std::{collections::BTreeSet, fmt::Display};
+
+pub const PUZZLE_PROGRESS_KEY: &str = "puzzles";
+
+const BUILTIN_PACKS: [&str; 1] = [include_str!("../assets/puzzles/first-steps.txt")];
+
+/// Where pack files are looked for on native, one `.txt` file per pack.
+#[cfg(not(target_arch = "wasm32"))]
+const PACKS_DIR: &str = "puzzles";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "easy"),
+            Difficulty::Medium => write!(f, "medium"),
+            Difficulty::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Level {
+    pub title: String,
+    pub difficulty: Difficulty,
+    pub minefield: Minefield,
+}
+
+impl Level {
+    pub fn mines(&self) -> usize {
+        self.minefield
+            .cells
+            .iter()
+            .filter(|cell| cell.kind == CellKind::Mine)
+            .count()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Pack {
+    pub title: String,
+    pub levels: Vec<Level>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PackError {
+    /// The file doesn't follow the pack format, at the given 1-based line.
+    Syntax { line: usize, message: String },
+    /// The solver can't finish the level from its given state.
+    NotGuessFree { level: String },
+    /// The pack file couldn't be read.
+    Read(String),
+}
+
+impl Display for PackError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            PackError::NotGuessFree { level } => {
+                write!(f, "level {level:?} can't be finished without guessing")
+            }
+            PackError::Read(message) => write!(f, "pack file: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for PackError {}
+
+fn syntax(line: usize, message: impl Into<String>) -> PackError {
+    PackError::Syntax {
+        line: line + 1,
+        message: message.into(),
+    }
+}
+
+/// Checks the board's characters and numbers before handing it to
+/// `Minefield::parse`, which trusts its input.
+fn parse_board(rows: &[(usize, &str)]) -> Result<Minefield, PackError> {
+    let Some(&(first, _)) = rows.first() else {
+        return Err(syntax(0, "missing board"));
+    };
+
+    for &(line, row) in rows {
+        if row.len() != rows[0].1.len() {
+            return Err(syntax(line, "rows differ in length"));
+        }
+
+        if let Some(c) = row.chars().find(|c| !".mfF012345678".contains(*c)) {
+            return Err(syntax(line, format!("invalid cell {c:?}")));
+        }
+
+        // The numbers next to a wrong flag would count more flags than mines.
+        if let Some(x) = row.find('f') {
+            return Err(syntax(
+                line,
+                format!("flag without a mine at column {}", x + 1),
+            ));
+        }
+    }
+
+    let board = rows.iter().map(|(_, row)| *row).collect::<Vec<_>>();
+    let minefield = Minefield::parse(&board.join("\n"));
+
+    for (y, &(line, row)) in rows.iter().enumerate() {
+        for (x, c) in row.chars().enumerate() {
+            if c.to_digit(10)
+                .is_some_and(|n| n as usize != minefield.count_mines(x, y))
+            {
+                return Err(syntax(line, format!("wrong number at column {}", x + 1)));
+            }
+        }
+    }
+
+    if !minefield
+        .cells
+        .iter()
+        .any(|cell| cell.state == CellState::Opened)
+    {
+        return Err(syntax(first, "no opened cells"));
+    }
+
+    Ok(minefield)
+}
+
+impl Pack {
+    pub fn parse(s: &str) -> Result<Pack, PackError> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.starts_with('#'))
+            .peekable();
+
+        let title = match lines.next() {
+            Some((_, line)) if line.starts_with("pack:") => line["pack:".len()..].trim(),
+            Some((line, _)) => return Err(syntax(line, "expected `pack:`")),
+            None => return Err(syntax(0, "empty pack")),
+        };
+
+        let mut levels = Vec::new();
+
+        loop {
+            while lines.next_if(|(_, line)| line.is_empty()).is_some() {}
+
+            let Some((line, header)) = lines.next() else {
+                break;
+            };
+
+            let Some(title) = header.strip_prefix("level:") else {
+                return Err(syntax(line, "expected `level:`"));
+            };
+
+            let difficulty = match lines.next() {
+                Some((line, header)) => match header.strip_prefix("difficulty:").map(str::trim) {
+                    Some("easy") => Difficulty::Easy,
+                    Some("medium") => Difficulty::Medium,
+                    Some("hard") => Difficulty::Hard,
+                    _ => return Err(syntax(line, "expected `difficulty: easy|medium|hard`")),
+                },
+                None => return Err(syntax(line, "missing difficulty")),
+            };
+
+            let mut rows = Vec::new();
+
+            while let Some(row) = lines.next_if(|(_, line)| !line.is_empty()) {
+                rows.push(row);
+            }
+
+            if rows.is_empty() {
+                return Err(syntax(line, "missing board"));
+            }
+
+            let level = Level {
+                title: title.trim().to_string(),
+                difficulty,
+                minefield: parse_board(&rows)?,
+            };
+
+            let mut solved = level.minefield.clone();
+            solve(&mut solved);
+
+            if !solved.is_solved() || solved.is_lost() {
+                return Err(PackError::NotGuessFree { level: level.title });
+            }
+
+            levels.push(level);
+        }
+
+        Ok(Pack {
+            title: title.to_string(),
+            levels,
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_file(path: &std::path::Path) -> Result<Pack, PackError> {
+        let pack = std::fs::read_to_string(path).map_err(|err| PackError::Read(err.to_string()))?;
+
+        Pack::parse(&pack)
+    }
+
+    /// Identifies a level across sessions.
+    fn level_id(&self, level: usize) -> String {
+        format!("{}/{}", self.title, self.levels[level].title)
+    }
+}
+
+/// The packs that ship with the game and, on native, the ones in the
+/// `puzzles` directory. Packs that fail to load are logged and left out.
+pub fn available_packs() -> Vec<Pack> {
+    let mut packs = Vec::new();
+
+    packs.extend(BUILTIN_PACKS.iter().map(|pack| Pack::parse(pack)));
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(entries) = std::fs::read_dir(PACKS_DIR) {
+        let mut paths = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+            .collect::<Vec<_>>();
+
+        paths.sort();
+        packs.extend(paths.iter().map(|path| Pack::load_file(path)));
+    }
+
+    packs
+        .into_iter()
+        .filter_map(|pack| {
+            pack.map_err(|err| log::warn!("Skipping puzzle pack: {err}"))
+                .ok()
+        })
+        .collect()
+}
+
+/// The levels the player has finished.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PuzzleProgress {
+    completed: BTreeSet<String>,
+}
+
+impl PuzzleProgress {
+    pub fn is_completed(&self, pack: &Pack, level: usize) -> bool {
+        self.completed.contains(&pack.level_id(level))
+    }
+
+    pub fn complete(&mut self, pack: &Pack, level: usize) {
+        self.completed.insert(pack.level_id(level));
+    }
+
+    pub fn encode(&self) -> String {
+        self.completed
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn decode(s: &str) -> PuzzleProgress {
+        PuzzleProgress {
+            completed: s.lines().map(str::to_string).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_packs() {
+        for pack in BUILTIN_PACKS {
+            assert!(Pack::parse(pack).is_ok_and(|pack| !pack.levels.is_empty()));
+        }
+    }
+
+    #[test]
+    fn test_pack_errors() {
+        let guess = "pack: Test\n\nlevel: Coin flip\ndifficulty: easy\n1m\n..\n";
+        assert_eq!(
+            Pack::parse(guess).unwrap_err(),
+            PackError::NotGuessFree {
+                level: "Coin flip".to_string()
+            }
+        );
+
+        let wrong_number = "pack: Test\n\nlevel: Wrong\ndifficulty: easy\n2m\n";
+        assert!(matches!(
+            Pack::parse(wrong_number),
+            Err(PackError::Syntax { line: 5, .. })
+        ));
+
+        let wrong_flag = "pack: Test\n\nlevel: Wrong\ndifficulty: easy\n1f\nm.\n";
+        assert!(matches!(
+            Pack::parse(wrong_flag),
+            Err(PackError::Syntax { line: 5, .. })
+        ));
+    }
+
+    #[test]
+    fn test_load_file() {
+        let path = std::env::temp_dir().join("minesweeper-test-pack.txt");
+        std::fs::write(&path, BUILTIN_PACKS[0]).unwrap();
+
+        let pack = Pack::load_file(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(pack.is_ok_and(|pack| !pack.levels.is_empty()));
+        assert!(matches!(Pack::load_file(&path), Err(PackError::Read(_))));
+    }
+
+    #[test]
+    fn test_puzzle_progress() {
+        let pack = Pack::parse(BUILTIN_PACKS[0]).unwrap();
+        let mut progress = PuzzleProgress::default();
+
+        progress.complete(&pack, 1);
+
+        let progress = PuzzleProgress::decode(&progress.encode());
+        assert!(progress.is_completed(&pack, 1));
+        assert!(!progress.is_completed(&pack, 0));
+    }
+}
@@ -0,0 +1,425 @@
+//! Skins: the sprites of the board, header and frames, loaded from a sprite
+//! sheet and a manifest naming the tile of each role.
+//!
+//! A manifest starts with a `skin:` line and a `sheet:` line giving the sheet's
+//! file name next to it. Every other line is a role name followed by the
+//! `x y width height` of its tile in the sheet, in pixels. Roles a manifest
+//! leaves out keep the built-in sprites.
+
+use crate::utils::load_image;
+use egui::{
+    include_image, load::SizedTexture, pos2, ColorImage, Context, Id, Image, ImageSource, Rect,
+    TextureHandle, TextureId, TextureOptions, Vec2,
+};
+use std::{collections::HashMap, fmt::Display, sync::Arc};
+
+pub const SKIN_KEY: &str = "skin";
+
+const BUNDLED_SKINS: [(&str, &[u8]); 1] = [(
+    include_str!("../assets/skins/midnight/manifest.txt"),
+    include_bytes!("../assets/skins/midnight/sheet.png"),
+)];
+
+/// Where skins are looked for on native, one directory per skin holding a
+/// `manifest.txt`.
+#[cfg(not(target_arch = "wasm32"))]
+const SKINS_DIR: &str = "skins";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Role {
+    Empty,
+    /// An opened cell with 1 to 8 mines around it.
+    Number(usize),
+    Hidden,
+    Flag,
+    Question,
+    OpenedMine,
+    RevealedMine,
+    IncorrectFlag,
+    Face,
+    FacePressed,
+    FacePressing,
+    FaceWon,
+    FaceLost,
+    /// A digit of the counters.
+    Digit(usize),
+    /// The corner between the light and dark edges of a frame, by border
+    /// width.
+    Corner(usize),
+}
+
+impl Role {
+    pub fn all() -> Vec<Role> {
+        let mut roles = vec![Role::Empty];
+        roles.extend((1..=8).map(Role::Number));
+        roles.extend([
+            Role::Hidden,
+            Role::Flag,
+            Role::Question,
+            Role::OpenedMine,
+            Role::RevealedMine,
+            Role::IncorrectFlag,
+            Role::Face,
+            Role::FacePressed,
+            Role::FacePressing,
+            Role::FaceWon,
+            Role::FaceLost,
+        ]);
+        roles.extend((0..10).map(Role::Digit));
+        roles.extend([Role::Corner(2), Role::Corner(3)]);
+        roles
+    }
+
+    /// The role's name in manifests.
+    pub fn name(self) -> String {
+        match self {
+            Role::Empty => "empty".to_string(),
+            Role::Number(n) => n.to_string(),
+            Role::Hidden => "hidden".to_string(),
+            Role::Flag => "flag".to_string(),
+            Role::Question => "question".to_string(),
+            Role::OpenedMine => "mine-opened".to_string(),
+            Role::RevealedMine => "mine-revealed".to_string(),
+            Role::IncorrectFlag => "flag-incorrect".to_string(),
+            Role::Face => "face".to_string(),
+            Role::FacePressed => "face-pressed".to_string(),
+            Role::FacePressing => "face-pressing".to_string(),
+            Role::FaceWon => "face-won".to_string(),
+            Role::FaceLost => "face-lost".to_string(),
+            Role::Digit(n) => format!("digit-{n}"),
+            Role::Corner(n) => format!("corner-{n}"),
+        }
+    }
+
+    fn builtin(self) -> ImageSource<'static> {
+        match self {
+            Role::Number(1) => include_image!("../assets/1.png"),
+            Role::Number(2) => include_image!("../assets/2.png"),
+            Role::Number(3) => include_image!("../assets/3.png"),
+            Role::Number(4) => include_image!("../assets/4.png"),
+            Role::Number(5) => include_image!("../assets/5.png"),
+            Role::Number(6) => include_image!("../assets/6.png"),
+            Role::Number(7) => include_image!("../assets/7.png"),
+            Role::Number(8) => include_image!("../assets/8.png"),
+            Role::Empty | Role::Number(_) => include_image!("../assets/empty.png"),
+            Role::Hidden => include_image!("../assets/hidden.png"),
+            Role::Flag => include_image!("../assets/flag.png"),
+            Role::Question => include_image!("../assets/question.png"),
+            Role::OpenedMine => include_image!("../assets/opened_mine.png"),
+            Role::RevealedMine => include_image!("../assets/revealed_mine.png"),
+            Role::IncorrectFlag => include_image!("../assets/incorrect_flag.png"),
+            Role::Face => include_image!("../assets/face.png"),
+            Role::FacePressed => include_image!("../assets/face-pressed.png"),
+            Role::FacePressing => include_image!("../assets/face-pressing.png"),
+            Role::FaceWon => include_image!("../assets/face-won.png"),
+            Role::FaceLost => include_image!("../assets/face-lost.png"),
+            Role::Digit(0) => include_image!("../assets/d-0.png"),
+            Role::Digit(1) => include_image!("../assets/d-1.png"),
+            Role::Digit(2) => include_image!("../assets/d-2.png"),
+            Role::Digit(3) => include_image!("../assets/d-3.png"),
+            Role::Digit(4) => include_image!("../assets/d-4.png"),
+            Role::Digit(5) => include_image!("../assets/d-5.png"),
+            Role::Digit(6) => include_image!("../assets/d-6.png"),
+            Role::Digit(7) => include_image!("../assets/d-7.png"),
+            Role::Digit(8) => include_image!("../assets/d-8.png"),
+            Role::Digit(_) => include_image!("../assets/d-9.png"),
+            Role::Corner(2) => include_image!("../assets/margin-corner-2.png"),
+            Role::Corner(_) => include_image!("../assets/margin-corner-3.png"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SkinError {
+    /// The manifest doesn't follow the format, at the given 1-based line.
+    Syntax { line: usize, message: String },
+    /// The sprite sheet couldn't be read or decoded.
+    Sheet(String),
+}
+
+impl Display for SkinError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SkinError::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            SkinError::Sheet(message) => write!(f, "sprite sheet: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for SkinError {}
+
+fn syntax(line: usize, message: impl Into<String>) -> SkinError {
+    SkinError::Syntax {
+        line: line + 1,
+        message: message.into(),
+    }
+}
+
+/// A parsed manifest: the skin's name, its sheet's file name, and the pixel
+/// rectangle of each role it overrides.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Manifest {
+    pub name: String,
+    pub sheet: String,
+    pub tiles: Vec<(Role, [usize; 4])>,
+}
+
+impl Manifest {
+    pub fn parse(s: &str) -> Result<Manifest, SkinError> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let mut header = |key: &str| match lines.next() {
+            Some((line, header)) => header
+                .strip_prefix(key)
+                .map(|value| value.trim().to_string())
+                .ok_or_else(|| syntax(line, format!("expected `{key}`"))),
+            None => Err(syntax(0, format!("missing `{key}`"))),
+        };
+
+        let name = header("skin:")?;
+        let sheet = header("sheet:")?;
+        let roles = Role::all();
+        let mut tiles = Vec::new();
+
+        for (line, tile) in lines {
+            let fields = tile.split_whitespace().collect::<Vec<_>>();
+
+            let [role, x, y, width, height] = fields[..] else {
+                return Err(syntax(line, "expected `role x y width height`"));
+            };
+
+            let Some(role) = roles.iter().find(|r| r.name() == role) else {
+                return Err(syntax(line, format!("unknown role {role:?}")));
+            };
+
+            let mut rect = [0; 4];
+
+            for (value, field) in rect.iter_mut().zip([x, y, width, height]) {
+                *value = field
+                    .parse()
+                    .map_err(|_| syntax(line, format!("invalid number {field:?}")))?;
+            }
+
+            tiles.push((*role, rect));
+        }
+
+        Ok(Manifest { name, sheet, tiles })
+    }
+}
+
+/// One tile: its image and the part of the image's texture it covers.
+#[derive(Clone)]
+pub struct Sprite {
+    source: ImageSource<'static>,
+    uv: Rect,
+}
+
+impl Sprite {
+    /// Scaled without smoothing, to keep the pixel art crisp.
+    pub fn image(&self) -> Image<'static> {
+        load_image(self.source.clone()).uv(self.uv)
+    }
+
+    /// The texture and uv rectangle to paint the sprite with, once loaded.
+    pub fn texture(&self, ctx: &Context) -> Option<(TextureId, Rect)> {
+        let texture = self
+            .image()
+            .load_for_size(ctx, Vec2::splat(16.0))
+            .ok()?
+            .texture_id()?;
+
+        Some((texture, self.uv))
+    }
+}
+
+pub struct Skin {
+    pub name: String,
+    sprites: HashMap<Role, Sprite>,
+    /// Keeps the sheet's texture alive for as long as the skin is.
+    _sheet: Option<TextureHandle>,
+}
+
+impl Skin {
+    pub fn builtin() -> Skin {
+        Skin {
+            name: "Classic".to_string(),
+            sprites: Role::all()
+                .into_iter()
+                .map(|role| {
+                    let sprite = Sprite {
+                        source: role.builtin(),
+                        uv: Rect::from_min_max(pos2(0.0, 0.0), pos2(1.0, 1.0)),
+                    };
+
+                    (role, sprite)
+                })
+                .collect(),
+            _sheet: None,
+        }
+    }
+
+    /// Uploads `sheet`, a PNG, and cuts it into the tiles `manifest` names.
+    pub fn load(ctx: &Context, manifest: &str, sheet: &[u8]) -> Result<Skin, SkinError> {
+        let manifest = Manifest::parse(manifest)?;
+
+        let image = image::load_from_memory(sheet)
+            .map_err(|err| SkinError::Sheet(err.to_string()))?
+            .to_rgba8();
+        let size = [image.width() as usize, image.height() as usize];
+
+        // Checked, since the manifest may name any rectangle at all.
+        let fits = |start: usize, len: usize, max: usize| {
+            start.checked_add(len).is_some_and(|end| end <= max)
+        };
+
+        for (role, [x, y, width, height]) in &manifest.tiles {
+            if !fits(*x, *width, size[0]) || !fits(*y, *height, size[1]) {
+                return Err(SkinError::Sheet(format!(
+                    "{} lies outside the {}x{} sheet",
+                    role.name(),
+                    size[0],
+                    size[1]
+                )));
+            }
+        }
+
+        let texture = ctx.load_texture(
+            format!("skin-{}", manifest.name),
+            ColorImage::from_rgba_unmultiplied(size, image.as_raw()),
+            TextureOptions::NEAREST,
+        );
+
+        let mut skin = Skin::builtin();
+        skin.name = manifest.name;
+
+        for (role, [x, y, width, height]) in manifest.tiles {
+            let (x, y) = (x as f32 / size[0] as f32, y as f32 / size[1] as f32);
+
+            let sprite = Sprite {
+                source: ImageSource::Texture(SizedTexture::new(
+                    texture.id(),
+                    Vec2::new(width as f32, height as f32),
+                )),
+                uv: Rect::from_min_size(
+                    pos2(x, y),
+                    Vec2::new(
+                        width as f32 / size[0] as f32,
+                        height as f32 / size[1] as f32,
+                    ),
+                ),
+            };
+
+            skin.sprites.insert(role, sprite);
+        }
+
+        skin._sheet = Some(texture);
+
+        Ok(skin)
+    }
+
+    /// Reads `manifest.txt` and the sheet it names from `dir`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_dir(ctx: &Context, dir: &std::path::Path) -> Result<Skin, SkinError> {
+        let read_error = |err: std::io::Error| SkinError::Sheet(err.to_string());
+
+        let manifest = std::fs::read_to_string(dir.join("manifest.txt")).map_err(read_error)?;
+        let sheet =
+            std::fs::read(dir.join(Manifest::parse(&manifest)?.sheet)).map_err(read_error)?;
+
+        Skin::load(ctx, &manifest, &sheet)
+    }
+
+    pub fn sprite(&self, role: Role) -> &Sprite {
+        &self.sprites[&role]
+    }
+
+    pub fn image(&self, role: Role) -> Image<'static> {
+        self.sprite(role).image()
+    }
+
+    /// The skin widgets draw with.
+    pub fn current(ctx: &Context) -> Arc<Skin> {
+        ctx.data_mut(|data| {
+            data.get_temp_mut_or_insert_with(Id::new(SKIN_KEY), || Arc::new(Skin::builtin()))
+                .clone()
+        })
+    }
+
+    pub fn set_current(ctx: &Context, skin: Arc<Skin>) {
+        ctx.data_mut(|data| data.insert_temp(Id::new(SKIN_KEY), skin));
+    }
+}
+
+/// The built-in skin, the bundled ones and, on native, the ones in the
+/// `skins` directory. Skins that fail to load are logged and left out.
+pub fn available_skins(ctx: &Context) -> Vec<Arc<Skin>> {
+    let mut skins = vec![Ok(Skin::builtin())];
+
+    skins.extend(
+        BUNDLED_SKINS
+            .iter()
+            .map(|(manifest, sheet)| Skin::load(ctx, manifest, sheet)),
+    );
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(dirs) = std::fs::read_dir(SKINS_DIR) {
+        skins.extend(dirs.flatten().map(|dir| Skin::load_dir(ctx, &dir.path())));
+    }
+
+    skins
+        .into_iter()
+        .filter_map(|skin| {
+            skin.map_err(|err| log::warn!("Skipping skin: {err}"))
+                .ok()
+                .map(Arc::new)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_skins() {
+        let ctx = Context::default();
+
+        for (manifest, sheet) in BUNDLED_SKINS {
+            let skin = Skin::load(&ctx, manifest, sheet).unwrap();
+            assert_eq!(skin.sprites.len(), Role::all().len());
+            assert_eq!(
+                Manifest::parse(manifest).unwrap().tiles.len(),
+                Role::all().len()
+            );
+        }
+    }
+
+    #[test]
+    fn test_manifest_errors() {
+        assert_eq!(
+            Manifest::parse("skin: Test\nsheet: sheet.png\nmine 0 0 16 16\n"),
+            Err(SkinError::Syntax {
+                line: 3,
+                message: "unknown role \"mine\"".to_string()
+            })
+        );
+        assert!(matches!(
+            Manifest::parse("skin: Test\nflag 0 0 16 16\n"),
+            Err(SkinError::Syntax { line: 2, .. })
+        ));
+
+        let (_, sheet) = BUNDLED_SKINS[0];
+        let huge = format!(
+            "skin: Test\nsheet: sheet.png\nempty {} 0 16 16\n",
+            usize::MAX
+        );
+        assert!(matches!(
+            Skin::load(&Context::default(), &huge, sheet),
+            Err(SkinError::Sheet(_))
+        ));
+    }
+}
@@ -1,13 +1,14 @@
-use crate::minefield::{CellState, Minefield};
+use crate::minefield::{CellKind, CellState, Minefield};
 use nalgebra::{DMatrix, DMatrixView, DVector, DVectorView};
+use rand::{seq::SliceRandom, Rng};
 use std::{
-    iter::{once, repeat},
+    iter::{once, repeat_n},
     ops::Range,
 };
 
 fn get_hidden_cells(minefield: &Minefield, all: bool) -> Vec<usize> {
     (0..(minefield.height * minefield.width))
-        .filter(|idx| minefield.cells[*idx].state == CellState::Hidden)
+        .filter(|idx| minefield.cells[*idx].state.is_hidden())
         .filter(|idx| {
             all || minefield.neighboring_open(*idx % minefield.width, *idx / minefield.width)
         })
@@ -61,7 +62,7 @@ fn create_system(
 
     if include_total_mines {
         a_inner = a_inner_iter
-            .chain(repeat(1).take(hidden_cells.len()))
+            .chain(repeat_n(1, hidden_cells.len()))
             .collect::<Vec<u8>>();
 
         let undiscovered_mines = minefield.total_mines() - minefield.total_flags();
@@ -314,7 +315,7 @@ fn analyze_solutions_bm(
         return false;
     }
 
-    for i in 0..hidden_cells.len() {
+    for (i, &hidden_idx) in hidden_cells.iter().enumerate() {
         let first = solutions[0] & (1 << i);
 
         if solutions.iter().any(|sol| sol & (1 << i) != first) {
@@ -324,9 +325,9 @@ fn analyze_solutions_bm(
         changed = true;
 
         if first == 0 {
-            minefield.open(hidden_cells[i] % mf_width, hidden_cells[i] / mf_width);
+            minefield.open(hidden_idx % mf_width, hidden_idx / mf_width);
         } else {
-            minefield.cells[hidden_cells[i]].state = CellState::Flagged;
+            minefield.cells[hidden_idx].state = CellState::Flagged;
         }
     }
 
@@ -346,6 +347,612 @@ pub fn solve_step_pruning_bm(minefield: &mut Minefield, with_total_mines: bool)
     analyze_solutions_bm(minefield, &hidden_cells, &solutions)
 }
 
+const MAX_SEARCH_NODES: usize = 1_000_000;
+
+/// Like `find_solutions_pruning_bm`, but also prunes once a number can't get
+/// enough mines from the cells left, which keeps large components tractable.
+/// Returns false if it gave up after visiting `budget` nodes.
+fn find_component_solutions(
+    a: &[u64],
+    x: &[u32],
+    size: usize,
+    b: u64,
+    i: usize,
+    budget: &mut usize,
+    solutions: &mut Vec<u64>,
+) -> bool {
+    if *budget == 0 {
+        return false;
+    }
+
+    *budget -= 1;
+
+    let undecided = u64::MAX.checked_shl(i as u32).unwrap_or(0);
+
+    for (a, x) in a.iter().zip(x.iter()) {
+        let mines = (a & b).count_ones();
+
+        if mines > *x || mines + (a & undecided).count_ones() < *x {
+            return true;
+        }
+    }
+
+    if i == size {
+        solutions.push(b);
+        return true;
+    }
+
+    find_component_solutions(a, x, size, b, i + 1, budget, solutions)
+        && find_component_solutions(a, x, size, b | (1 << i), i + 1, budget, solutions)
+}
+
+/// Cells on the frontier that are linked by the numbers around them, and every
+/// way mines can be placed among them. `solutions` is `None` if there are too
+/// many to enumerate.
+struct Component {
+    cells: Vec<usize>,
+    solutions: Option<Vec<u64>>,
+}
+
+impl Component {
+    /// Which numbers of mines the component can hold.
+    fn mine_counts(&self) -> Vec<bool> {
+        let mut counts = vec![false; self.cells.len() + 1];
+
+        match &self.solutions {
+            Some(solutions) => {
+                for solution in solutions {
+                    counts[solution.count_ones() as usize] = true;
+                }
+            }
+            None => counts.fill(true),
+        }
+
+        counts
+    }
+}
+
+fn frontier_components(minefield: &Minefield) -> Vec<Component> {
+    let mf_width = minefield.width;
+
+    let constraints = (0..minefield.cells.len())
+        .filter(|idx| minefield.cells[*idx].state == CellState::Opened)
+        .map(|idx| {
+            let (x, y) = (idx % mf_width, idx / mf_width);
+
+            let hidden = minefield
+                .neighbors(x, y)
+                .map(|(x, y)| y * mf_width + x)
+                .filter(|idx| minefield.cells[*idx].state.is_hidden())
+                .collect::<Vec<_>>();
+
+            let value = minefield.count_mines(x, y) - minefield.count_flags(x, y);
+
+            (hidden, value as u32)
+        })
+        .filter(|(hidden, _)| !hidden.is_empty())
+        .collect::<Vec<_>>();
+
+    let mut constraints_of = vec![Vec::new(); minefield.cells.len()];
+
+    for (i, (hidden, _)) in constraints.iter().enumerate() {
+        for idx in hidden {
+            constraints_of[*idx].push(i);
+        }
+    }
+
+    let mut visited = vec![false; minefield.cells.len()];
+    let mut components = Vec::new();
+
+    for first in 0..minefield.cells.len() {
+        if visited[first] || constraints_of[first].is_empty() {
+            continue;
+        }
+
+        // Cells are collected breadth first, so cells that share numbers end
+        // up close together and the search can prune early.
+        visited[first] = true;
+        let mut cells = vec![first];
+        let mut next = 0;
+
+        while next < cells.len() {
+            for constraint in &constraints_of[cells[next]] {
+                for idx in &constraints[*constraint].0 {
+                    if !visited[*idx] {
+                        visited[*idx] = true;
+                        cells.push(*idx);
+                    }
+                }
+            }
+
+            next += 1;
+        }
+
+        if cells.len() > u64::BITS as usize {
+            components.push(Component {
+                cells,
+                solutions: None,
+            });
+            continue;
+        }
+
+        let mut component_constraints = cells
+            .iter()
+            .flat_map(|idx| constraints_of[*idx].iter().copied())
+            .collect::<Vec<_>>();
+        component_constraints.sort_unstable();
+        component_constraints.dedup();
+
+        let (a, x): (Vec<u64>, Vec<u32>) = component_constraints
+            .into_iter()
+            .map(|constraint| {
+                let (hidden, value) = &constraints[constraint];
+
+                let mask = cells
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, idx)| hidden.contains(idx))
+                    .fold(0, |mask, (i, _)| mask | (1 << i));
+
+                (mask, *value)
+            })
+            .unzip();
+
+        let mut solutions = Vec::new();
+        let mut budget = MAX_SEARCH_NODES;
+        let complete =
+            find_component_solutions(&a, &x, cells.len(), 0, 0, &mut budget, &mut solutions);
+
+        components.push(Component {
+            cells,
+            solutions: complete.then_some(solutions),
+        });
+    }
+
+    components
+}
+
+/// Every total the counts in `sets` can add up to, picking one from each.
+fn sums(sets: &[Vec<bool>]) -> Vec<bool> {
+    sets.iter().fold(vec![true], |acc, set| {
+        let mut sum = vec![false; acc.len() + set.len() - 1];
+
+        for (a, _) in acc.iter().enumerate().filter(|(_, a)| **a) {
+            for (b, _) in set.iter().enumerate().filter(|(_, b)| **b) {
+                sum[a + b] = true;
+            }
+        }
+
+        sum
+    })
+}
+
+/// The hidden cells that can be proven safe and the ones that can be proven
+/// mines, as `(safe, mines)`, without changing the board. Each independent
+/// group of frontier cells is solved on its own, then their mine counts are
+/// combined with the total number of mines to reason about the cells no
+/// number touches.
+pub fn find_deductions(minefield: &Minefield, with_total_mines: bool) -> (Vec<usize>, Vec<usize>) {
+    let components = frontier_components(minefield);
+
+    let counts = components
+        .iter()
+        .map(Component::mine_counts)
+        .collect::<Vec<_>>();
+
+    let frontier = components.iter().map(|c| c.cells.len()).sum::<usize>();
+    let interior = minefield.total_hidden() - frontier;
+    let remaining = minefield.total_mines() as isize - minefield.total_flags() as isize;
+
+    let fits = |frontier_mines: usize| {
+        let interior_mines = remaining - frontier_mines as isize;
+        !with_total_mines || (0..=interior as isize).contains(&interior_mines)
+    };
+
+    let mut safe = Vec::new();
+    let mut mines = Vec::new();
+
+    for (i, component) in components.iter().enumerate() {
+        let Some(solutions) = &component.solutions else {
+            continue;
+        };
+
+        let others = sums(
+            &counts
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, counts)| counts.clone())
+                .collect::<Vec<_>>(),
+        );
+
+        let feasible = solutions
+            .iter()
+            .filter(|solution| {
+                others.iter().enumerate().any(|(other_mines, possible)| {
+                    *possible && fits(solution.count_ones() as usize + other_mines)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if feasible.is_empty() {
+            continue;
+        }
+
+        let any_mine = feasible.iter().fold(0, |acc, solution| acc | **solution);
+        let all_mines = feasible
+            .iter()
+            .fold(u64::MAX, |acc, solution| acc & **solution);
+
+        for (bit, idx) in component.cells.iter().enumerate() {
+            if any_mine & (1 << bit) == 0 {
+                safe.push(*idx);
+            } else if all_mines & (1 << bit) != 0 {
+                mines.push(*idx);
+            }
+        }
+    }
+
+    if with_total_mines && interior > 0 {
+        let interior_mines = sums(&counts)
+            .iter()
+            .enumerate()
+            .filter(|(frontier_mines, possible)| **possible && fits(*frontier_mines))
+            .map(|(frontier_mines, _)| remaining - frontier_mines as isize)
+            .collect::<Vec<_>>();
+
+        let is_interior = |idx: &usize| {
+            minefield.cells[*idx].state.is_hidden()
+                && !components.iter().any(|c| c.cells.contains(idx))
+        };
+
+        if !interior_mines.is_empty() && interior_mines.iter().all(|mines| *mines == 0) {
+            safe.extend((0..minefield.cells.len()).filter(is_interior));
+        } else if !interior_mines.is_empty()
+            && interior_mines
+                .iter()
+                .all(|mines| *mines == interior as isize)
+        {
+            mines.extend((0..minefield.cells.len()).filter(is_interior));
+        }
+    }
+
+    (safe, mines)
+}
+
+/// A random layout that keeps every opened number and the total number of
+/// mines, with the hidden cell `idx` a mine or not as asked. Flags stay in
+/// place but aren't trusted. `None` if no layout fits, or if a group of
+/// frontier cells is too large to enumerate.
+pub fn resample_layout(
+    minefield: &Minefield,
+    rng: &mut impl Rng,
+    idx: usize,
+    mine: bool,
+) -> Option<Minefield> {
+    let unflagged = minefield.without_flags();
+    let components = frontier_components(&unflagged);
+
+    // The solutions of each component that agree with `idx`, by mine count.
+    let mut by_count = Vec::new();
+
+    for component in &components {
+        let mut solutions = vec![Vec::new(); component.cells.len() + 1];
+        let bit = component.cells.iter().position(|cell| *cell == idx);
+
+        for &solution in component.solutions.as_ref()? {
+            if bit.is_none_or(|bit| (solution & (1 << bit) != 0) == mine) {
+                solutions[solution.count_ones() as usize].push(solution);
+            }
+        }
+
+        by_count.push(solutions);
+    }
+
+    let interior = (0..unflagged.cells.len())
+        .filter(|idx| {
+            unflagged.cells[*idx].state.is_hidden()
+                && !components.iter().any(|c| c.cells.contains(idx))
+        })
+        .collect::<Vec<_>>();
+
+    let interior_mines = match (interior.contains(&idx), mine) {
+        (true, true) => 1..=interior.len(),
+        (true, false) => 0..=interior.len() - 1,
+        (false, _) => 0..=interior.len(),
+    };
+
+    let counts = by_count
+        .iter()
+        .map(|solutions| solutions.iter().map(|s| !s.is_empty()).collect())
+        .collect::<Vec<Vec<bool>>>();
+    let rest = (0..=counts.len())
+        .map(|i| sums(&counts[i..]))
+        .collect::<Vec<_>>();
+    let total = minefield.total_mines();
+
+    // Whether the components from `i` on can still leave the interior a
+    // number of mines it allows, once `used` are placed.
+    let fits = |i: usize, used: usize| {
+        rest[i].iter().enumerate().any(|(mines, possible)| {
+            *possible
+                && total
+                    .checked_sub(used + mines)
+                    .is_some_and(|left| interior_mines.contains(&left))
+        })
+    };
+
+    let mut layout = minefield.clone();
+    let mut used = 0;
+
+    for (i, (component, solutions)) in components.iter().zip(&by_count).enumerate() {
+        let options = (0..solutions.len())
+            .filter(|count| !solutions[*count].is_empty() && fits(i + 1, used + count))
+            .collect::<Vec<_>>();
+
+        // Counts with more solutions are likelier, so that each solution is
+        // about as likely as any other.
+        let count = *options
+            .choose_weighted(rng, |count| solutions[*count].len())
+            .ok()?;
+        let solution = solutions[count].choose(rng)?;
+
+        for (bit, cell) in component.cells.iter().enumerate() {
+            layout.cells[*cell].kind = if solution & (1 << bit) != 0 {
+                CellKind::Mine
+            } else {
+                CellKind::Empty
+            };
+        }
+
+        used += count;
+    }
+
+    let mut left = total - used;
+
+    if !interior_mines.contains(&left) {
+        return None;
+    }
+
+    for cell in &interior {
+        layout.cells[*cell].kind = CellKind::Empty;
+    }
+
+    if interior.contains(&idx) && mine {
+        layout.cells[idx].kind = CellKind::Mine;
+        left -= 1;
+    }
+
+    let others = interior
+        .into_iter()
+        .filter(|cell| *cell != idx)
+        .collect::<Vec<_>>();
+
+    for i in rand::seq::index::sample(rng, others.len(), left) {
+        layout.cells[others[i]].kind = CellKind::Mine;
+    }
+
+    Some(layout)
+}
+
+/// A move the solver can prove, and the opened numbers that prove it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hint {
+    pub cell: usize,
+    pub mine: bool,
+    pub reasons: Vec<usize>,
+}
+
+/// The provable move that needs the fewest numbers, safe cells first, or
+/// `None` if nothing can be proven. Flags aren't trusted, and mines that are
+/// already flagged aren't hinted.
+pub fn find_hint(minefield: &Minefield) -> Option<Hint> {
+    let unflagged = minefield.without_flags();
+    let (safe, mines) = find_deductions(&unflagged, true);
+    let components = frontier_components(&unflagged);
+    let width = minefield.width;
+
+    let reasons = |cells: &[usize]| {
+        let mut reasons = cells
+            .iter()
+            .flat_map(|idx| minefield.neighbors(idx % width, idx / width))
+            .map(|(x, y)| y * width + x)
+            .filter(|idx| minefield.cells[*idx].state == CellState::Opened)
+            .collect::<Vec<_>>();
+        reasons.sort_unstable();
+        reasons.dedup();
+        reasons
+    };
+
+    // Cells no number touches are only proven by every number, through the
+    // total count.
+    let frontier = components
+        .iter()
+        .flat_map(|c| c.cells.iter().copied())
+        .collect::<Vec<_>>();
+
+    safe.iter()
+        .map(|idx| (*idx, false))
+        .chain(
+            mines
+                .iter()
+                .filter(|idx| minefield.cells[**idx].state != CellState::Flagged)
+                .map(|idx| (*idx, true)),
+        )
+        .map(|(cell, mine)| {
+            let group = components
+                .iter()
+                .find(|c| c.cells.contains(&cell))
+                .map_or(&frontier, |c| &c.cells);
+
+            Hint {
+                cell,
+                mine,
+                reasons: reasons(group),
+            }
+        })
+        .min_by_key(|hint| hint.reasons.len())
+}
+
+/// `ln(n choose k)`, which stays finite where the binomial itself wouldn't.
+fn ln_binomial(n: usize, k: usize) -> f64 {
+    (0..k).map(|i| ((n - i) as f64 / (i + 1) as f64).ln()).sum()
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut product = vec![0.0; a.len() + b.len() - 1];
+
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            product[i + j] += a * b;
+        }
+    }
+
+    product
+}
+
+/// The chance of each cell being a mine, with every layout that keeps the
+/// opened numbers and the total number of mines equally likely. Flags aren't
+/// trusted. `None` for opened cells and for cells in a group of frontier cells
+/// too large to enumerate, which is then counted as if no number touched it.
+pub fn mine_probabilities(minefield: &Minefield) -> Vec<Option<f64>> {
+    let minefield = minefield.without_flags();
+    let components = frontier_components(&minefield);
+    let mut probabilities = vec![None; minefield.cells.len()];
+
+    // How many solutions of each component hold each number of mines.
+    let counts = components
+        .iter()
+        .map(|component| {
+            let size = component.cells.len();
+
+            match &component.solutions {
+                Some(solutions) => {
+                    let mut counts = vec![0.0; size + 1];
+
+                    for solution in solutions {
+                        counts[solution.count_ones() as usize] += 1.0;
+                    }
+
+                    counts
+                }
+                None => (0..=size).map(|k| ln_binomial(size, k).exp()).collect(),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let frontier = components.iter().map(|c| c.cells.len()).sum::<usize>();
+    let interior = minefield.total_hidden() - frontier;
+    let total = minefield.total_mines();
+
+    // The ways to place the other mines in the interior, for each number of
+    // frontier mines, relative to the likeliest number.
+    let ln_weights = (0..=frontier)
+        .map(|mines| match total.checked_sub(mines) {
+            Some(rest) if rest <= interior => ln_binomial(interior, rest),
+            _ => f64::NEG_INFINITY,
+        })
+        .collect::<Vec<_>>();
+    let max = ln_weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    if max == f64::NEG_INFINITY {
+        return probabilities;
+    }
+
+    let weights = ln_weights
+        .iter()
+        .map(|ln_weight| (ln_weight - max).exp())
+        .collect::<Vec<_>>();
+
+    let mut prefixes = vec![vec![1.0]];
+
+    for counts in &counts {
+        prefixes.push(convolve(prefixes.last().unwrap(), counts));
+    }
+
+    let mut suffixes = vec![vec![1.0]];
+
+    for counts in counts.iter().rev() {
+        suffixes.push(convolve(suffixes.last().unwrap(), counts));
+    }
+
+    suffixes.reverse();
+
+    let all = &prefixes[components.len()];
+    let layouts = all.iter().zip(&weights).map(|(n, w)| n * w).sum::<f64>();
+
+    if layouts == 0.0 {
+        return probabilities;
+    }
+
+    for (i, component) in components.iter().enumerate() {
+        let Some(solutions) = &component.solutions else {
+            continue;
+        };
+
+        let others = convolve(&prefixes[i], &suffixes[i + 1]);
+
+        // The layouts each solution of the component is part of, by its
+        // number of mines.
+        let per_solution = (0..=component.cells.len())
+            .map(|mines| {
+                others
+                    .iter()
+                    .enumerate()
+                    .map(|(other_mines, n)| n * weights[mines + other_mines])
+                    .sum::<f64>()
+            })
+            .collect::<Vec<_>>();
+
+        for (bit, idx) in component.cells.iter().enumerate() {
+            let mine = solutions
+                .iter()
+                .filter(|solution| *solution & (1 << bit) != 0)
+                .map(|solution| per_solution[solution.count_ones() as usize])
+                .sum::<f64>();
+
+            probabilities[*idx] = Some(mine / layouts);
+        }
+    }
+
+    if interior > 0 {
+        let interior_mines = all
+            .iter()
+            .zip(&weights)
+            .enumerate()
+            .map(|(mines, (n, w))| n * w * total.saturating_sub(mines) as f64)
+            .sum::<f64>();
+        let probability = interior_mines / layouts / interior as f64;
+
+        for (idx, cell) in minefield.cells.iter().enumerate() {
+            if cell.state.is_hidden() && !components.iter().any(|c| c.cells.contains(&idx)) {
+                probabilities[idx] = Some(probability);
+            }
+        }
+    }
+
+    probabilities
+}
+
+/// Flags and opens every cell [`find_deductions`] proves. Unlike the other
+/// solvers this works on boards with any number of hidden cells, as long as
+/// no single group of frontier cells exceeds 64 cells.
+pub fn solve_step_components(minefield: &mut Minefield, with_total_mines: bool) -> bool {
+    let mf_width = minefield.width;
+
+    let (safe, mines) = find_deductions(minefield, with_total_mines);
+
+    for idx in &mines {
+        minefield.cells[*idx].state = CellState::Flagged;
+    }
+
+    for idx in &safe {
+        minefield.open(idx % mf_width, idx / mf_width);
+    }
+
+    !safe.is_empty() || !mines.is_empty()
+}
+
 fn get_unknowns_in_chuck(
     minefield: &Minefield,
     chuck_x: Range<usize>,
@@ -366,7 +973,7 @@ fn get_unknowns_in_chuck(
                 || *y == chuck_y.end
                 || (chuck_x.contains(x)
                     && chuck_y.contains(y)
-                    && (minefield.cells[y * mf_width + x].state == CellState::Hidden))
+                    && (minefield.cells[y * mf_width + x].state.is_hidden()))
         })
         .filter(|(x, y)| minefield.neighboring_open(*x, *y))
         .map(|(x, y)| y * mf_width + x)
@@ -555,23 +1162,83 @@ pub fn solve_chucking(minefield: &mut Minefield, chuck_size: usize, chuck_overla
 }
 
 pub fn solve_step(minefield: &mut Minefield) -> bool {
-    solve_step_pruning_bm(minefield, true)
+    solve_step_components(minefield, true)
 }
 
 pub fn solve(minefield: &mut Minefield) {
     while solve_step(minefield) {}
 }
 
+/// Applies every deduction that follows from a single number on its own.
+pub fn solve_step_basic(minefield: &mut Minefield) -> bool {
+    let mf_width = minefield.width;
+
+    let mut changed = false;
+
+    for idx in 0..minefield.cells.len() {
+        let (x, y) = (idx % mf_width, idx / mf_width);
+
+        if minefield.cells[idx].state != CellState::Opened {
+            continue;
+        }
+
+        let hidden = minefield.count_hidden(x, y);
+
+        if hidden == 0 {
+            continue;
+        }
+
+        let remaining = minefield.count_mines(x, y) - minefield.count_flags(x, y);
+
+        if remaining != 0 && remaining != hidden {
+            continue;
+        }
+
+        changed = true;
+
+        let neighbors = minefield.neighbors(x, y).collect::<Vec<_>>();
+
+        for (x, y) in neighbors {
+            if !minefield.cells[y * mf_width + x].state.is_hidden() {
+                continue;
+            }
+
+            if remaining == 0 {
+                minefield.open(x, y);
+            } else {
+                minefield.cells[y * mf_width + x].state = CellState::Flagged;
+            }
+        }
+    }
+
+    changed
+}
+
+/// Solves the minefield preferring single number deductions, and returns how
+/// many steps needed the full constraint solver.
+pub fn solve_rated(minefield: &mut Minefield) -> usize {
+    let mut advanced_steps = 0;
+
+    loop {
+        if solve_step_basic(minefield) {
+            continue;
+        }
+
+        if !solve_step(minefield) {
+            return advanced_steps;
+        }
+
+        advanced_steps += 1;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use rand::{rngs::StdRng, SeedableRng};
 
-    use super::*;
+    use crate::minefield::FirstClick;
 
-    fn solve_bf_aux(mut minefield: Minefield) -> Minefield {
-        solve_bf(&mut minefield);
-        minefield
-    }
+    use super::*;
 
     #[test]
     fn test_solve_step() {
@@ -650,7 +1317,7 @@ mod tests {
 
         for i in 0..100 {
             eprintln!("{}", i);
-            let mut minefield = Minefield::random_start(&mut rng, 4, 4, 3);
+            let mut minefield = Minefield::random_start(&mut rng, 4, 4, 3).unwrap();
             let mut minefield2 = minefield.clone();
             let mut minefield3 = minefield.clone();
 
@@ -669,9 +1336,10 @@ mod tests {
     fn test_solve_equality_without_total_mines() {
         let mut rng = StdRng::seed_from_u64(0);
 
-        for i in 0..100 {
+        // Small boards, so that the bitmask solver stays quick in debug builds.
+        for i in 0..50 {
             eprintln!("{}", i);
-            let mut minefield1 = Minefield::random_start(&mut rng, 6, 6, 3);
+            let mut minefield1 = Minefield::random_start(&mut rng, 5, 5, 3).unwrap();
             let mut minefield2 = minefield1.clone();
 
             eprintln!("{}", minefield1);
@@ -683,6 +1351,165 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_solve_components_equality() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..100 {
+            let mut minefield1 = Minefield::random_start(&mut rng, 5, 5, 4).unwrap();
+            let mut minefield2 = minefield1.clone();
+
+            solve_bm(&mut minefield1);
+            while solve_step_components(&mut minefield2, true) {}
+
+            assert_eq!(minefield1, minefield2);
+        }
+    }
+
+    #[test]
+    fn test_solve_expert() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let mut solved = 0;
+
+        for _ in 0..5 {
+            let mut minefield =
+                Minefield::start_at(&mut rng, 30, 16, 99, (15, 8), FirstClick::Opening).unwrap();
+            let hidden = minefield.total_hidden();
+
+            solve(&mut minefield);
+
+            assert!(!minefield.is_lost());
+            assert!(minefield
+                .cells
+                .iter()
+                .all(|cell| cell.state != CellState::Flagged || cell.kind == CellKind::Mine));
+
+            solved += hidden - minefield.total_hidden();
+        }
+
+        // Far more than 64 cells are hidden, past what one bitmask can hold.
+        assert!(solved > 0);
+    }
+
+    #[test]
+    fn test_mine_probabilities() {
+        let mut rng = StdRng::seed_from_u64(0);
+
+        for _ in 0..50 {
+            let minefield = Minefield::random_start(&mut rng, 4, 4, 3).unwrap();
+            let hidden = (0..minefield.cells.len())
+                .filter(|idx| minefield.cells[*idx].state.is_hidden())
+                .collect::<Vec<_>>();
+
+            // Counts every layout that agrees with the board.
+            let mut layouts = 0.0;
+            let mut mines = vec![0.0; minefield.cells.len()];
+
+            for mask in 0u32..1 << hidden.len() {
+                if mask.count_ones() as usize != minefield.total_mines() {
+                    continue;
+                }
+
+                let mut layout = minefield.clone();
+
+                for (bit, idx) in hidden.iter().enumerate() {
+                    layout.cells[*idx].kind = if mask & (1 << bit) != 0 {
+                        CellKind::Mine
+                    } else {
+                        CellKind::Empty
+                    };
+                }
+
+                let fits = (0..layout.cells.len())
+                    .filter(|idx| layout.cells[*idx].state == CellState::Opened)
+                    .all(|idx| {
+                        let (x, y) = (idx % layout.width, idx / layout.width);
+                        layout.count_mines(x, y) == minefield.count_mines(x, y)
+                    });
+
+                if fits {
+                    layouts += 1.0;
+
+                    for (bit, idx) in hidden.iter().enumerate() {
+                        if mask & (1 << bit) != 0 {
+                            mines[*idx] += 1.0;
+                        }
+                    }
+                }
+            }
+
+            let probabilities = mine_probabilities(&minefield);
+
+            for idx in 0..minefield.cells.len() {
+                match probabilities[idx] {
+                    Some(probability) => {
+                        assert!((probability - mines[idx] / layouts).abs() < 1e-9)
+                    }
+                    None => assert_eq!(minefield.cells[idx].state, CellState::Opened),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_find_hint() {
+        let minefield = Minefield::parse(".m..m\n11..m");
+
+        assert_eq!(
+            find_hint(&minefield),
+            Some(Hint {
+                cell: 2,
+                mine: false,
+                reasons: vec![5, 6],
+            })
+        );
+
+        assert_eq!(find_hint(&Minefield::parse("1m\n..")), None);
+    }
+
+    #[test]
+    fn test_resample_layout() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut minefield = Minefield::parse(".m..m\n11..m");
+        minefield.cells[3].state = CellState::Flagged;
+
+        // The cell next to the 1-1 is provably safe.
+        assert_eq!(resample_layout(&minefield, &mut rng, 2, true), None);
+
+        for (idx, mine) in [(0, true), (0, false), (3, true), (8, false)] {
+            let layout = resample_layout(&minefield, &mut rng, idx, mine).unwrap();
+
+            assert_eq!(layout.cells[idx].kind == CellKind::Mine, mine);
+            assert_eq!(layout.total_mines(), minefield.total_mines());
+            assert_eq!(layout.cells[3].state, CellState::Flagged);
+
+            for idx in [5, 6] {
+                let (x, y) = (idx % layout.width, idx / layout.width);
+                assert_eq!(layout.count_mines(x, y), minefield.count_mines(x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_rated() {
+        let mut minefield = Minefield::parse(
+            r#"0011
+               001m"#,
+        );
+        assert_eq!(solve_rated(&mut minefield), 0);
+        assert!(minefield.is_solved());
+
+        // The 1-2-1 pattern needs all three numbers at once.
+        let mut minefield = Minefield::parse(
+            r#"m.m
+               121
+               000"#,
+        );
+        assert_eq!(solve_rated(&mut minefield), 1);
+        assert!(minefield.is_solved());
+    }
+
     #[test]
     fn test_solve_bf() {
         let mut minefield = Minefield::parse(
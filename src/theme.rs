@@ -0,0 +1,155 @@
+//! Colors of the retro frames, buttons and modals, and of the egui widgets
+//! inside them.
+
+use egui::{Color32, Context, Id, Visuals};
+
+pub const THEME_KEY: &str = "theme";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub dark: bool,
+    pub background: Color32,
+    /// The lit edges of frames.
+    pub highlight: Color32,
+    /// The shaded edges of frames.
+    pub shadow: Color32,
+    pub text: Color32,
+    /// Dims the game behind modals.
+    pub overlay: Color32,
+    /// The skin whose tiles match the theme.
+    pub skin: &'static str,
+}
+
+impl Theme {
+    pub const LIGHT: Theme = Theme {
+        dark: false,
+        background: Color32::from_rgb(192, 192, 192),
+        highlight: Color32::WHITE,
+        shadow: Color32::from_rgb(128, 128, 128),
+        text: Color32::BLACK,
+        overlay: Color32::from_black_alpha(100),
+        skin: "Classic",
+    };
+
+    pub const DARK: Theme = Theme {
+        dark: true,
+        background: Color32::from_rgb(58, 60, 70),
+        highlight: Color32::from_rgb(104, 108, 124),
+        shadow: Color32::from_rgb(28, 29, 34),
+        text: Color32::from_rgb(220, 222, 230),
+        overlay: Color32::from_black_alpha(160),
+        skin: "Midnight",
+    };
+
+    pub fn visuals(&self) -> Visuals {
+        let mut visuals = if self.dark {
+            Visuals::dark()
+        } else {
+            Visuals::light()
+        };
+
+        visuals.override_text_color = Some(self.text);
+        visuals
+    }
+
+    /// The theme widgets draw with.
+    pub fn current(ctx: &Context) -> Theme {
+        ctx.data(|data| data.get_temp(Id::new(THEME_KEY)))
+            .unwrap_or(Theme::LIGHT)
+    }
+
+    /// Also switches the egui visuals, if they don't match the theme. Checked
+    /// every call, since eframe resets them when the system theme changes.
+    pub fn set_current(ctx: &Context, theme: Theme) {
+        if Theme::current(ctx) != theme {
+            ctx.data_mut(|data| data.insert_temp(Id::new(THEME_KEY), theme));
+        }
+
+        let visuals = theme.visuals();
+
+        if ctx.style().visuals != visuals {
+            ctx.set_visuals(visuals);
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ThemePreference {
+    /// Follows the OS, falling back to light when it doesn't say.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemePreference {
+    pub fn resolve(self, system: Option<eframe::Theme>) -> Theme {
+        match (self, system) {
+            (ThemePreference::Dark, _) | (ThemePreference::System, Some(eframe::Theme::Dark)) => {
+                Theme::DARK
+            }
+            _ => Theme::LIGHT,
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            ThemePreference::System => ThemePreference::Light,
+            ThemePreference::Light => ThemePreference::Dark,
+            ThemePreference::Dark => ThemePreference::System,
+        }
+    }
+
+    pub fn encode(self) -> String {
+        match self {
+            ThemePreference::System => "System",
+            ThemePreference::Light => "Light",
+            ThemePreference::Dark => "Dark",
+        }
+        .to_string()
+    }
+
+    pub fn decode(s: &str) -> Option<Self> {
+        match s {
+            "System" => Some(ThemePreference::System),
+            "Light" => Some(ThemePreference::Light),
+            "Dark" => Some(ThemePreference::Dark),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_preference() {
+        let system = ThemePreference::default();
+
+        assert_eq!(system.resolve(Some(eframe::Theme::Dark)), Theme::DARK);
+        assert_eq!(system.resolve(None), Theme::LIGHT);
+        assert_eq!(
+            ThemePreference::Light.resolve(Some(eframe::Theme::Dark)),
+            Theme::LIGHT
+        );
+
+        for preference in [system, system.next(), system.next().next()] {
+            assert_eq!(
+                ThemePreference::decode(&preference.encode()),
+                Some(preference)
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_current() {
+        let ctx = Context::default();
+        Theme::set_current(&ctx, Theme::DARK);
+
+        // As eframe does when the system theme changes.
+        ctx.set_visuals(Visuals::light());
+        Theme::set_current(&ctx, Theme::DARK);
+        assert_eq!(ctx.style().visuals, Theme::DARK.visuals());
+    }
+}
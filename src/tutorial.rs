@@ -0,0 +1,136 @@
+//! A guided tutorial of small boards, each teaching one pattern the solver
+//! finds. The learner has to make a move the pattern proves right.
+
+use crate::{
+    minefield::{CellState, Minefield},
+    solver::find_deductions,
+};
+
+pub struct Lesson {
+    pub title: &'static str,
+    pub prompt: &'static str,
+    /// Why the move is right, shown once the learner makes it.
+    pub reason: &'static str,
+    board: &'static str,
+}
+
+impl Lesson {
+    pub fn minefield(&self) -> Minefield {
+        Minefield::parse(self.board)
+    }
+}
+
+pub const LESSONS: [Lesson; 6] = [
+    Lesson {
+        title: "1-1",
+        prompt: "The two 1s share a mine. Open a cell that is safe.",
+        reason: "The left 1 has its mine in one of the two cells above it, and the \
+                 right 1 touches both of them too. Its mine is used up, so the other \
+                 cells it touches are safe.",
+        board: ".m..m\n11..m",
+    },
+    Lesson {
+        title: "1-2",
+        prompt: "Flag the mine the 1-2 points to.",
+        reason: "The 1 puts one mine in the two cells above it, which the 2 also \
+                 touches. The 2 needs one more, and the only place left for it is the \
+                 cell the 1 doesn't touch.",
+        board: ".mm..m\n122...",
+    },
+    Lesson {
+        title: "1-2-1",
+        prompt: "Open a safe cell or flag a mine.",
+        reason: "The 2 gets at most one mine from each 1 next to it, so it gets \
+                 exactly one from each. The mines sit above the 1s, and the cell \
+                 above the 2 is safe.",
+        board: "m.m..m\n121...",
+    },
+    Lesson {
+        title: "1-2-2-1",
+        prompt: "Open a safe cell or flag a mine.",
+        reason: "A mine above the outer side of a 1 would leave the 2 next to it one \
+                 short. So the mines sit above the two 2s, and the outer cells are safe.",
+        board: ".mm..m\n1221..",
+    },
+    Lesson {
+        title: "Corner",
+        prompt: "Flag the mine in the corner.",
+        reason: "The middle 1 touches every cell the outer 1s do. If the corner were \
+                 safe, each outer 1 would need its own mine, giving the middle 1 two. \
+                 So the corner is the mine, and the middle 1's other cells are safe.",
+        board: "m1...m\n11...m\n......",
+    },
+    Lesson {
+        title: "Mine counting",
+        prompt: "Check the mine counter, then open a safe cell.",
+        reason: "The 2 needs two mines around it, and only two are left on the \
+                 board. None can be anywhere else, so the cells the 2 doesn't touch \
+                 are safe.",
+        board: "...\n.m2\n..m",
+    },
+];
+
+/// Whether going from `before` to `after` opened one cell or flagged one
+/// mine that `before` proves.
+pub fn is_right_move(before: &Minefield, after: &Minefield) -> bool {
+    let (safe, mines) = find_deductions(before, true);
+
+    let flagged = mines.iter().any(|&idx| {
+        let mut minefield = before.clone();
+        minefield.cells[idx].state = CellState::Flagged;
+        minefield == *after
+    });
+
+    let opened = safe.iter().any(|&idx| {
+        let mut minefield = before.clone();
+        minefield.open(idx % minefield.width, idx / minefield.width);
+        minefield == *after
+    });
+
+    flagged || opened
+}
+
+/// Where the learner is in the tutorial.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tutorial {
+    pub lesson: usize,
+    /// How the last move went, if one was made.
+    pub right: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lessons_have_a_move() {
+        for lesson in &LESSONS {
+            let minefield = lesson.minefield();
+            let (safe, mines) = find_deductions(&minefield, true);
+
+            assert!(!safe.is_empty() || !mines.is_empty(), "{}", lesson.title);
+            assert!(!minefield.is_lost() && !minefield.is_solved());
+        }
+
+        // Mine counting is only needed for the last lesson.
+        let counting = LESSONS[5].minefield();
+        assert_eq!(find_deductions(&counting, false), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_is_right_move() {
+        let before = LESSONS[0].minefield();
+
+        let mut right = before.clone();
+        right.open(2, 0);
+        assert!(is_right_move(&before, &right));
+
+        let mut wrong = before.clone();
+        wrong.open(0, 0);
+        assert!(!is_right_move(&before, &wrong));
+
+        let mut flagged = before.clone();
+        flagged.cells[1].state = CellState::Flagged;
+        assert!(!is_right_move(&before, &flagged));
+    }
+}
@@ -1,6 +1,6 @@
 use egui::{Image, ImageSource, TextureOptions};
 
-pub fn load_image(src: ImageSource) -> Image<'_> {
+pub fn load_image(src: ImageSource<'_>) -> Image<'_> {
     Image::new(src)
         .fit_to_original_size(1.0)
         .texture_options(TextureOptions::NEAREST)
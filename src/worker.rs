@@ -0,0 +1,362 @@
+//! Guess-free generation in a Web Worker, so the page keeps rendering while a
+//! board is searched for. The page and the worker exchange plain text
+//! messages: the page sends an encoded `GeneratorConfig`, and the worker
+//! answers with any number of `Message::Progress` followed by one
+//! `Message::Found`.
+
+use crate::{
+    generating::{DifficultyBand, GeneratorConfig, GeneratorProgress, Search, SearchStep},
+    minefield::{FirstClick, Minefield, PlacementError},
+};
+use web_time::{Duration, Instant};
+
+/// How often the worker reports on its progress.
+const REPORT_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    Progress(GeneratorProgress),
+    Found(Minefield),
+}
+
+impl Message {
+    /// A header line, followed by the board if the message carries one.
+    pub fn encode(&self) -> String {
+        match self {
+            Message::Progress(progress) => format!(
+                "progress {} {} {}\n{}",
+                progress.attempts,
+                progress.frontier,
+                progress.elapsed.as_millis(),
+                progress
+                    .best
+                    .as_ref()
+                    .map(Minefield::format)
+                    .unwrap_or_default()
+            ),
+            Message::Found(minefield) => format!("found\n{}", minefield.format()),
+        }
+    }
+
+    pub fn decode(s: &str) -> Option<Message> {
+        let (header, board) = s.split_once('\n')?;
+        let board = (!board.trim().is_empty()).then(|| Minefield::parse(board));
+        let mut fields = header.split(' ');
+
+        match fields.next()? {
+            "progress" => {
+                let mut number = || fields.next()?.parse::<u64>().ok();
+
+                Some(Message::Progress(GeneratorProgress {
+                    attempts: number()? as usize,
+                    frontier: number()? as usize,
+                    elapsed: Duration::from_millis(number()?),
+                    best: board,
+                }))
+            }
+            "found" => board.map(Message::Found),
+            _ => None,
+        }
+    }
+}
+
+pub fn encode_config(config: &GeneratorConfig) -> String {
+    let bounds = |bounds: Option<(usize, usize)>| match bounds {
+        Some((min, max)) => format!("{min}-{max}"),
+        None => "any".to_string(),
+    };
+    let first_click = match config.first_click {
+        FirstClick::Safe => "safe",
+        FirstClick::Opening => "opening",
+        FirstClick::Unprotected => "unprotected",
+    };
+
+    format!(
+        "generate {} {} {} {} {} {} {} {}",
+        config.start,
+        config.width,
+        config.height,
+        config.mines,
+        config.seed,
+        first_click,
+        bounds(config.band.three_bv),
+        bounds(config.band.advanced_steps)
+    )
+}
+
+pub fn decode_config(s: &str) -> Option<GeneratorConfig> {
+    let fields = s.split(' ').collect::<Vec<_>>();
+
+    let ["generate", start, width, height, mines, seed, first_click, three_bv, advanced_steps] =
+        fields[..]
+    else {
+        return None;
+    };
+
+    let bounds = |bounds: &str| -> Option<Option<(usize, usize)>> {
+        if bounds == "any" {
+            return Some(None);
+        }
+
+        let (min, max) = bounds.split_once('-')?;
+
+        Some(Some((min.parse().ok()?, max.parse().ok()?)))
+    };
+
+    Some(GeneratorConfig {
+        start: start.parse().ok()?,
+        width: width.parse().ok()?,
+        height: height.parse().ok()?,
+        mines: mines.parse().ok()?,
+        seed: seed.parse().ok()?,
+        first_click: match first_click {
+            "safe" => FirstClick::Safe,
+            "opening" => FirstClick::Opening,
+            "unprotected" => FirstClick::Unprotected,
+            _ => return None,
+        },
+        band: DifficultyBand {
+            three_bv: bounds(three_bv)?,
+            advanced_steps: bounds(advanced_steps)?,
+        },
+    })
+}
+
+/// Searches for a guess-free board on the calling thread, calling `report`
+/// every `REPORT_INTERVAL`. Attempts run in the same order as in the other
+/// backends, so the same seed gives the same board.
+pub fn generate(
+    config: GeneratorConfig,
+    mut report: impl FnMut(&GeneratorProgress),
+) -> Result<Minefield, PlacementError> {
+    let started = Instant::now();
+    let mut last_report = started;
+    let mut progress = GeneratorProgress::default();
+    let mut attempt = 0;
+
+    loop {
+        progress.attempts = attempt + 1;
+        let mut search = Search::new(config, attempt)?;
+
+        loop {
+            match search.step() {
+                SearchStep::Solving => {}
+                SearchStep::Stuck => progress.record_stuck(&search.minefield),
+                SearchStep::Failed => break,
+                SearchStep::Found(minefield) => return Ok(minefield),
+            }
+
+            if last_report.elapsed() >= REPORT_INTERVAL {
+                progress.elapsed = started.elapsed();
+                report(&progress);
+                last_report = Instant::now();
+            }
+        }
+
+        attempt += 1;
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub use web::{run_worker, WorkerGuessfreeGenerator};
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use super::{decode_config, encode_config, generate, Message};
+    use crate::generating::{
+        GeneratorConfig, GeneratorError, GeneratorProgress, GeneratorStatus, GuessfreeGenerator,
+    };
+    use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+    use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+    use web_sys::{DedicatedWorkerGlobalScope, ErrorEvent, MessageEvent, Worker};
+    use web_time::Instant;
+
+    /// The loader trunk generates for the `generator_worker` binary.
+    const WORKER_URL: &str = "./generator_worker_loader.js";
+
+    /// The entry point of the worker. Every config posted to it is answered
+    /// with its board. The search can't be interrupted from inside, so the
+    /// page cancels it by terminating the worker.
+    pub fn run_worker() {
+        let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+
+        let onmessage = Closure::<dyn FnMut(MessageEvent)>::new(|event: MessageEvent| {
+            let Some(config) = event.data().as_string().and_then(|s| decode_config(&s)) else {
+                log::warn!(
+                    "Generator worker got an invalid message: {:?}",
+                    event.data()
+                );
+                return;
+            };
+
+            let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+            let post = |message: Message| {
+                if let Err(err) = scope.post_message(&JsValue::from_str(&message.encode())) {
+                    log::warn!("Generator worker failed to post a message: {err:?}");
+                }
+            };
+
+            match generate(config, |progress| post(Message::Progress(progress.clone()))) {
+                Ok(minefield) => post(Message::Found(minefield)),
+                // The page checks the config before sending it.
+                Err(err) => log::warn!("Generator worker got an unusable config: {err}"),
+            }
+        });
+
+        scope.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+        onmessage.forget();
+    }
+
+    pub struct WorkerGuessfreeGenerator {
+        started: Instant,
+        worker: Worker,
+        inbox: Rc<RefCell<VecDeque<Result<Message, GeneratorError>>>>,
+        progress: GeneratorProgress,
+        cancelled: bool,
+        // Kept alive for as long as the worker can call them.
+        _onmessage: Closure<dyn FnMut(MessageEvent)>,
+        _onerror: Closure<dyn FnMut(ErrorEvent)>,
+    }
+
+    impl WorkerGuessfreeGenerator {
+        pub fn new(config: GeneratorConfig) -> Result<Self, JsValue> {
+            let worker = Worker::new(WORKER_URL)?;
+            let inbox = Rc::new(RefCell::new(VecDeque::new()));
+
+            let onmessage = Closure::<dyn FnMut(MessageEvent)>::new({
+                let inbox = inbox.clone();
+                move |event: MessageEvent| {
+                    let message = event.data().as_string().and_then(|s| Message::decode(&s));
+                    inbox
+                        .borrow_mut()
+                        .push_back(message.ok_or(GeneratorError::Disconnected));
+                }
+            });
+            let onerror = Closure::<dyn FnMut(ErrorEvent)>::new({
+                let inbox = inbox.clone();
+                move |event: ErrorEvent| {
+                    log::warn!("Generator worker failed: {}", event.message());
+                    inbox
+                        .borrow_mut()
+                        .push_back(Err(GeneratorError::Disconnected));
+                }
+            });
+
+            worker.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+            worker.set_onerror(Some(onerror.as_ref().unchecked_ref()));
+            worker.post_message(&JsValue::from_str(&encode_config(&config)))?;
+
+            Ok(WorkerGuessfreeGenerator {
+                started: Instant::now(),
+                worker,
+                inbox,
+                progress: GeneratorProgress::default(),
+                cancelled: false,
+                _onmessage: onmessage,
+                _onerror: onerror,
+            })
+        }
+    }
+
+    impl GuessfreeGenerator for WorkerGuessfreeGenerator {
+        fn run(&mut self) -> Result<GeneratorStatus, GeneratorError> {
+            if self.cancelled {
+                return Err(GeneratorError::Cancelled);
+            }
+
+            while let Some(message) = self.inbox.borrow_mut().pop_front() {
+                match message? {
+                    Message::Progress(progress) => self.progress = progress,
+                    Message::Found(minefield) => return Ok(GeneratorStatus::Found(minefield)),
+                }
+            }
+
+            Ok(GeneratorStatus::StillSolving(self.progress()))
+        }
+
+        fn progress(&self) -> GeneratorProgress {
+            GeneratorProgress {
+                elapsed: self.started.elapsed(),
+                ..self.progress.clone()
+            }
+        }
+
+        fn cancel(&mut self) {
+            self.worker.terminate();
+            self.cancelled = true;
+        }
+    }
+
+    impl Drop for WorkerGuessfreeGenerator {
+        fn drop(&mut self) {
+            self.worker.terminate();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generating::{GeneratorStatus, GuessfreeGenerator, ParallelGuessfreeGenerator};
+
+    fn config() -> GeneratorConfig {
+        GeneratorConfig {
+            start: 14,
+            width: 6,
+            height: 6,
+            mines: 5,
+            seed: u64::MAX,
+            first_click: FirstClick::Opening,
+            band: DifficultyBand {
+                three_bv: Some((2, 30)),
+                advanced_steps: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_config_round_trip() {
+        assert_eq!(decode_config(&encode_config(&config())), Some(config()));
+        assert_eq!(decode_config("generate 1 2 3"), None);
+    }
+
+    #[test]
+    fn test_worker_generates_same_board() {
+        let config = GeneratorConfig {
+            seed: 7,
+            band: DifficultyBand::default(),
+            ..config()
+        };
+        let minefield = generate(config, |_| {}).unwrap();
+        let mut generator = ParallelGuessfreeGenerator::with_workers(config, 2);
+
+        let found = loop {
+            match generator.run().unwrap() {
+                GeneratorStatus::Found(found) => break found,
+                GeneratorStatus::StillSolving(_) => {
+                    std::thread::sleep(std::time::Duration::from_millis(1))
+                }
+            }
+        };
+
+        assert_eq!(minefield, found);
+
+        let message = Message::decode(&Message::Found(minefield.clone()).encode());
+        assert!(matches!(message, Some(Message::Found(decoded)) if decoded == minefield));
+
+        let progress = GeneratorProgress {
+            attempts: 3,
+            frontier: 4,
+            elapsed: Duration::from_millis(250),
+            best: None,
+        };
+        let Some(Message::Progress(decoded)) =
+            Message::decode(&Message::Progress(progress).encode())
+        else {
+            panic!("progress didn't round trip");
+        };
+        assert_eq!((decoded.attempts, decoded.frontier), (3, 4));
+        assert_eq!(decoded.elapsed, Duration::from_millis(250));
+        assert!(decoded.best.is_none());
+    }
+}